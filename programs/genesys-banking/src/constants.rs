@@ -1,16 +1,28 @@
 use anchor_lang::constant;
 
 #[constant]
-pub const MAX_RESERVE_TOKEN_AMOUNT: u64 = 10_000_000;
+pub const MAX_ANNUAL_RATE_BPS: u64 = 100_000;
 #[constant]
-pub const MAX_USER_DEPOSIT: u64 = 100_000;
-#[constant]
-pub const INTEREST_RATE_TENTHBPS: u64 = 35;
+pub const MAX_EARLY_WITHDRAWAL_PENALTY_BPS: u64 = 10_000;
 #[constant]
 pub const TOKEN_VAULT_SEED: &str = "token-vault";
 #[constant]
 pub const VAULT_INFO_SEED: &str = "vault-info";
 #[constant]
+pub const VAULT_ROLES_SEED: &str = "vault-roles";
+#[constant]
+pub const QUEUED_CLAIM_SEED: &str = "queued-claim";
+#[constant]
+pub const RATE_TIERS_SEED: &str = "rate-tiers";
+#[constant]
+pub const MAX_RATE_TIERS: usize = 8;
+#[constant]
+pub const PRODUCT_SEED: &str = "product";
+#[constant]
+pub const MAX_PRODUCT_NAME_LEN: usize = 32;
+#[constant]
 pub const USER_VAULT_SEED: &str = "user-vault";
 #[constant]
-pub const USER_DEPOSIT_INFO: &str = "user-deposit-info";
\ No newline at end of file
+pub const USER_DEPOSIT_INFO: &str = "user-deposit-info";
+#[constant]
+pub const USER_PROFILE_SEED: &str = "user-profile";
\ No newline at end of file
@@ -0,0 +1,571 @@
+use anchor_lang::prelude::*;
+
+use crate::instructions::set_role::Role;
+use crate::instructions::set_rate_tiers::RateTier;
+use crate::math::{Compounding, RateBasis};
+
+
+#[event]
+/// Emitted when a vault is initialized
+pub struct VaultInitialized {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The mint of the SPL token stored in the vault
+    pub token_mint: Pubkey,
+
+    /// The reserve
+    pub token_vault: Pubkey,
+
+    /// The vault admin
+    pub vault_admin: Pubkey,
+
+    /// Maximum number of tokens in vault (in base units)
+    pub max_tokens: u64,
+
+    /// Annual interest rate (in bps)
+    pub annual_rate_bps: u64,
+
+    /// Whether the annual rate is an APR or an APY
+    pub rate_basis: RateBasis,
+
+    /// Per-second rate derived from the annual rate (in `RAY`)
+    pub rate_per_second: u128,
+
+    /// Annual rate matured boxes keep earning (in bps)
+    pub post_maturity_rate_bps: u64,
+
+    /// How long after maturity boxes keep earning (in seconds)
+    pub max_post_maturity_seconds: u64,
+
+    /// Maximum number of tokens a user can lock in one deposit box (in base units)
+    pub max_user_deposit: u64,
+
+    /// Early withdrawal penalty (in bps)
+    pub early_withdrawal_penalty_bps: u64,
+
+    /// Tokens kept in the reserve on top of promised interest (in base units)
+    pub reserve_buffer: u64,
+
+    /// Whether only product deposits are accepted
+    pub products_only: bool,
+
+    /// Whether the mint is controlled by someone else
+    pub external_mint: bool,
+
+    /// Time initialized
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when the vault admin changes the vault parameters
+pub struct ConfigUpdated {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The risk manager who made the change
+    pub risk_manager: Pubkey,
+
+    /// New maximum number of tokens in vault (in base units)
+    pub max_tokens: u64,
+
+    /// New annual interest rate (in bps)
+    pub annual_rate_bps: u64,
+
+    /// Whether the annual rate is an APR or an APY
+    pub rate_basis: RateBasis,
+
+    /// New per-second rate derived from the annual rate (in `RAY`)
+    pub rate_per_second: u128,
+
+    /// New annual rate matured boxes keep earning (in bps)
+    pub post_maturity_rate_bps: u64,
+
+    /// New limit on how long after maturity boxes keep earning (in seconds)
+    pub max_post_maturity_seconds: u64,
+
+    /// New maximum number of tokens a user can lock in one deposit box (in base units)
+    pub max_user_deposit: u64,
+
+    /// New early withdrawal penalty (in bps)
+    pub early_withdrawal_penalty_bps: u64,
+
+    /// New reserve buffer (in base units)
+    pub reserve_buffer: u64,
+
+    /// Whether only product deposits are accepted
+    pub products_only: bool,
+
+    /// Time updated
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when the vault admin proposes a new admin
+pub struct AdminProposed {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The current vault admin
+    pub vault_admin: Pubkey,
+
+    /// The proposed admin
+    pub pending_admin: Pubkey,
+
+    /// Time proposed
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when a pending admin transfer is cancelled
+pub struct AdminTransferCancelled {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The vault admin
+    pub vault_admin: Pubkey,
+
+    /// The admin that was proposed
+    pub pending_admin: Pubkey,
+
+    /// Time cancelled
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when the proposed admin accepts the vault
+pub struct AdminTransferred {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The previous vault admin
+    pub previous_admin: Pubkey,
+
+    /// The new vault admin
+    pub vault_admin: Pubkey,
+
+    /// Time accepted
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when the vault admin assigns a role
+pub struct RoleAssigned {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The role assigned
+    pub role: Role,
+
+    /// The account now holding the role
+    pub account: Pubkey,
+
+    /// Time assigned
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when the risk manager replaces the rate table
+pub struct RateTiersUpdated {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The risk manager who made the change
+    pub risk_manager: Pubkey,
+
+    /// New brackets, sorted by their minimum lock duration
+    pub tiers: Vec<RateTier>,
+
+    /// Basis the bracket rates are quoted on
+    pub rate_basis: RateBasis,
+
+    /// Per-second rate of each bracket (scaled by 1e27)
+    pub rates_per_second: Vec<u128>,
+
+    /// Time updated
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when the risk manager publishes or changes a deposit product
+pub struct ProductUpdated {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The product account
+    pub product: Pubkey,
+
+    /// Id of the product within the vault
+    pub product_id: u64,
+
+    /// Name of the product
+    pub name: String,
+
+    /// Term of the product (in seconds)
+    pub seconds_locked: u64,
+
+    /// Annual interest rate of the product (in bps)
+    pub annual_rate_bps: u64,
+
+    /// Whether the annual rate is an APR or an APY
+    pub rate_basis: RateBasis,
+
+    /// Per-second rate derived from the annual rate (in `RAY`)
+    pub rate_per_second: u128,
+
+    /// Compounding convention of the product
+    pub compounding: Compounding,
+
+    /// Smallest deposit (in base units)
+    pub min_deposit: u64,
+
+    /// Largest deposit (in base units)
+    pub max_deposit: u64,
+
+    /// Maximum open principal across the product's boxes (in base units)
+    pub capacity: u64,
+
+    /// Whether the product takes new deposits
+    pub open: bool,
+
+    /// Time updated
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when the risk manager changes the vault's compounding convention
+pub struct CompoundingUpdated {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The risk manager who made the change
+    pub risk_manager: Pubkey,
+
+    /// New compounding convention
+    pub compounding: Compounding,
+
+    /// Per-second rate re-derived from the annual rate under the new convention (in `RAY`)
+    pub rate_per_second: u128,
+
+    /// Time updated
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when the pauser changes the pause flags
+pub struct PauseUpdated {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The pauser who made the change
+    pub pauser: Pubkey,
+
+    /// Whether new deposits and top-ups are paused
+    pub deposits_paused: bool,
+
+    /// Whether reserve refreshes are paused
+    pub refreshes_paused: bool,
+
+    /// Whether withdrawals are paused
+    pub withdrawals_paused: bool,
+
+    /// Whether interest payouts are frozen
+    pub emergency_mode: bool,
+
+    /// Time updated
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when the reserve is topped off
+pub struct ReserveRefreshed {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The reserve
+    pub token_vault: Pubkey,
+
+    /// Tokens minted into the reserve
+    pub amount: u64,
+
+    /// Queued interest paid out of the reserve right after the refresh
+    pub settled_interest: u64,
+
+    /// Reserve balance after the refresh and settlement
+    pub reserve_balance: u64,
+
+    /// Time refreshed
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when tokens are transferred into the reserve
+pub struct ReserveFunded {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The reserve
+    pub token_vault: Pubkey,
+
+    /// The account that funded the reserve
+    pub funder: Pubkey,
+
+    /// Tokens transferred into the reserve
+    pub amount: u64,
+
+    /// Total tokens transferred into the reserve so far
+    pub total_funded: u64,
+
+    /// Queued interest paid out of the reserve right after the funding
+    pub settled_interest: u64,
+
+    /// Reserve balance after the funding and settlement
+    pub reserve_balance: u64,
+
+    /// Time funded
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when the vault admin sweeps excess tokens out of the reserve
+pub struct ReserveSwept {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The reserve
+    pub token_vault: Pubkey,
+
+    /// The token account receiving the swept tokens
+    pub destination: Pubkey,
+
+    /// Tokens swept
+    pub amount: u64,
+
+    /// Reserve balance after the sweep
+    pub reserve_balance: u64,
+
+    /// Total tokens swept out of the reserve so far
+    pub total_swept: u64,
+
+    /// Time swept
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when a deposit box is opened
+pub struct Deposited {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The depositor
+    pub depositor: Pubkey,
+
+    /// The deposit box metadata account
+    pub deposit_info: Pubkey,
+
+    /// The token account holding the deposit
+    pub user_vault: Pubkey,
+
+    /// Index of the deposit box among the depositor's boxes
+    pub deposit_index: u64,
+
+    /// Deposited amount
+    pub deposit_lamports: u64,
+
+    /// Time in seconds locked
+    pub seconds_locked: u64,
+
+    /// Per-second rate locked in (in `RAY`)
+    pub rate_per_second: u128,
+
+    /// Most interest the box can be owed (at maturity, plus its post-maturity window)
+    pub promised_interest: u64,
+
+    /// The product the box was opened through, if any
+    pub product: Option<Pubkey>,
+
+    /// Time deposited
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when a deposit box is topped up
+pub struct ToppedUp {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The depositor
+    pub depositor: Pubkey,
+
+    /// The deposit box metadata account
+    pub deposit_info: Pubkey,
+
+    /// Index of the deposit box among the depositor's boxes
+    pub deposit_index: u64,
+
+    /// Tokens added by the depositor
+    pub top_up_lamports: u64,
+
+    /// Interest accrued so far, credited from the reserve
+    pub accrued_interest: u64,
+
+    /// Blended principal
+    pub deposit_lamports: u64,
+
+    /// Most interest the blended box can be owed (at the new maturity, plus its post-maturity window)
+    pub promised_interest: u64,
+
+    /// Time topped up (the lock restarts here)
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when a matured deposit box is withdrawn
+pub struct Withdrawn {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The depositor
+    pub depositor: Pubkey,
+
+    /// The deposit box metadata account (closed)
+    pub deposit_info: Pubkey,
+
+    /// Index of the deposit box among the depositor's boxes
+    pub deposit_index: u64,
+
+    /// Principal returned
+    pub deposit_lamports: u64,
+
+    /// Interest paid from the reserve
+    pub interest: u64,
+
+    /// Interest the reserve could not pay, queued as a `QueuedClaim`
+    pub queued_interest: u64,
+
+    /// Per-second rate the box was opened with (in `RAY`)
+    pub rate_per_second: u128,
+
+    /// Time withdrawn
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when a deposit box of the original singleton vault is withdrawn
+pub struct LegacyWithdrawn {
+
+    /// The original vault
+    pub legacy_vault_info: Pubkey,
+
+    /// The depositor
+    pub depositor: Pubkey,
+
+    /// The deposit box metadata account (closed)
+    pub deposit_info: Pubkey,
+
+    /// Principal locked in the box
+    pub deposit_lamports: u64,
+
+    /// Interest paid from the original reserve (zero if the box was paid out before)
+    pub interest: u64,
+
+    /// Time withdrawn
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when a deposit box is withdrawn before maturity
+pub struct EarlyWithdrawn {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The depositor
+    pub depositor: Pubkey,
+
+    /// The deposit box metadata account (closed)
+    pub deposit_info: Pubkey,
+
+    /// Index of the deposit box among the depositor's boxes
+    pub deposit_index: u64,
+
+    /// Principal locked in the box
+    pub deposit_lamports: u64,
+
+    /// Principal forfeited to the reserve
+    pub penalty: u64,
+
+    /// Interest credited by top-ups, returned to the reserve
+    pub forfeited_interest: u64,
+
+    /// Tokens returned to the depositor
+    pub payout: u64,
+
+    /// Time withdrawn
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when a deposit box is withdrawn in emergency mode
+pub struct EmergencyWithdrawn {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// The depositor
+    pub depositor: Pubkey,
+
+    /// The deposit box metadata account (closed)
+    pub deposit_info: Pubkey,
+
+    /// Index of the deposit box among the depositor's boxes
+    pub deposit_index: u64,
+
+    /// Principal returned
+    pub deposit_lamports: u64,
+
+    /// Time withdrawn
+    pub timestamp: i64,
+}
+
+#[event]
+/// Emitted when queued interest is paid to a withdrawn box, in queue order
+pub struct ClaimSettled {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// Position of the claim in the vault's queue
+    pub sequence: u64,
+
+    /// The depositor the interest was owed to
+    pub claimant: Pubkey,
+
+    /// The token account paid
+    pub token_account: Pubkey,
+
+    /// Interest paid
+    pub amount: u64,
+
+    /// Interest still queued for this claim
+    pub remaining: u64,
+
+    /// Time settled
+    pub timestamp: i64,
+}
@@ -0,0 +1,208 @@
+//! Writes the accounts preloaded into the test validator (see `Anchor.toml`) for states the program's
+//! instructions cannot reach. They are built from the program's own types, so they have to be rewritten
+//! whenever those layouts change:
+//!
+//! `cargo test -- --ignored write_`
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use spl_token::state::{Account as TokenAccountState, AccountState, Mint as MintState};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::instructions::initialize::{VaultInfo, VaultConfig};
+use crate::instructions::deposit::DepositInfo;
+use crate::instructions::withdraw_legacy::{LegacyVaultInfo, LegacyDepositInfo};
+use crate::constants::*;
+use crate::math::{Compounding, RAY};
+
+/// The test vault admin (`FRANK...json`)
+const VAULT_ADMIN: &str = "FRANKC3ibsaBW1o2qRuu3kspyaV4gHBuUfZ5uq9SXsqa";
+
+/// The test user (`CAVEY...json`)
+const USER: &str = "CAVEYgsWyeEAkAwXSA3tnqNhvFnztQkuwW1ZKfDBf9Za";
+
+/// Mint of the original singleton vault
+const LEGACY_MINT: &str = "7hJ7xrPRGgRRAokBgzdqJnRzz1Nu4UgmR3MzbpH4C3LD";
+
+/// Mint of the vault whose reserve is short
+const SHORT_MINT: &str = "6mtezEuB5PS4ZsrsLAhyp1h5vpBXmuDpfZ9fBzZFYPg8";
+
+fn key(address: &str) -> Pubkey {
+    Pubkey::from_str(address).unwrap()
+}
+
+fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &crate::ID).0
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| bits | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Writes `tests/fixtures/<set>/<name>.json` in the format of `solana account --output json`, rent exempt
+fn write_account(set: &str, name: &str, address: &Pubkey, owner: &Pubkey, data: &[u8]) {
+    let json = format!(
+        "{{\n  \"pubkey\": \"{}\",\n  \"account\": {{\n    \"lamports\": {},\n    \"data\": [\n      \"{}\",\n      \"base64\"\n    ],\n    \"owner\": \"{}\",\n    \"executable\": false,\n    \"rentEpoch\": 0\n  }}\n}}\n",
+        address,
+        Rent::default().minimum_balance(data.len()),
+        base64(data),
+        owner,
+    );
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../tests/fixtures").join(set);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(format!("{}.json", name)), json).unwrap();
+}
+
+fn mint_data(mint_authority: &Pubkey, freeze_authority: COption<Pubkey>, supply: u64) -> Vec<u8> {
+    let mut data = vec![0; MintState::LEN];
+    MintState {
+        mint_authority: COption::Some(*mint_authority),
+        supply,
+        decimals: 0,
+        is_initialized: true,
+        freeze_authority,
+    }.pack_into_slice(&mut data);
+    data
+}
+
+fn token_account_data(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Vec<u8> {
+    let mut data = vec![0; TokenAccountState::LEN];
+    TokenAccountState {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: AccountState::Initialized,
+        ..Default::default()
+    }.pack_into_slice(&mut data);
+    data
+}
+
+/// Serializes `account` behind its discriminator, padded to `space`
+fn program_account_data<T: AnchorSerialize>(discriminator: [u8; 8], account: &T, space: usize) -> Vec<u8> {
+    let mut data = discriminator.to_vec();
+    account.serialize(&mut data).unwrap();
+    assert!(data.len() <= space);
+    data.resize(space, 0);
+    data
+}
+
+#[test]
+#[ignore]
+/// A matured box of the original singleton vault, from before vaults were keyed by mint and boxes were
+/// indexed, which only `withdrawLegacy` can still pay out
+fn write_legacy_fixtures() {
+    let (vault_admin, user, mint) = (key(VAULT_ADMIN), key(USER), key(LEGACY_MINT));
+    let vault_info = pda(&[VAULT_INFO_SEED.as_bytes()]);
+    let token_vault = pda(&[TOKEN_VAULT_SEED.as_bytes()]);
+    let deposit_info = pda(&[USER_DEPOSIT_INFO.as_bytes(), user.as_ref()]);
+    let user_vault = pda(&[USER_VAULT_SEED.as_bytes(), user.as_ref()]);
+    let (reserve, principal) = (10_000, 1_000);
+
+    write_account("legacy", "mint", &mint, &spl_token::ID, &mint_data(&vault_admin, COption::Some(vault_admin), reserve + principal));
+    write_account("legacy", "token-vault", &token_vault, &spl_token::ID, &token_account_data(&mint, &vault_info, reserve));
+    write_account("legacy", "user-vault", &user_vault, &spl_token::ID, &token_account_data(&mint, &vault_info, principal));
+    write_account("legacy", "vault-info", &vault_info, &crate::ID, &program_account_data(
+        VaultInfo::discriminator(),
+        &LegacyVaultInfo {
+            max_tokens: 10_000_000,
+            interest_rate: 35,
+            vault_admin,
+            token_mint: mint,
+            token_vault,
+        },
+        LegacyVaultInfo::SPACE,
+    ));
+    write_account("legacy", "deposit-info", &deposit_info, &crate::ID, &program_account_data(
+        DepositInfo::discriminator(),
+        &LegacyDepositInfo {
+            seconds_locked: 100,
+            deposit_lamports: principal,
+            depositor: user,
+            deposit_time: 0,
+        },
+        LegacyDepositInfo::SPACE,
+    ));
+}
+
+#[test]
+#[ignore]
+/// A vault whose reserve cannot cover the interest it promised, which the program never allows: an external
+/// mint held by the vault admin, simple interest, a reserve of 600 tokens and two matured boxes of the user,
+/// each of 1,000 tokens and owed 1,000 tokens of interest
+fn write_short_reserve_fixtures() {
+    let (vault_admin, user, mint) = (key(VAULT_ADMIN), key(USER), key(SHORT_MINT));
+    let vault_info_key = pda(&[VAULT_INFO_SEED.as_bytes(), mint.as_ref()]);
+    let token_vault = pda(&[TOKEN_VAULT_SEED.as_bytes(), mint.as_ref()]);
+    let (reserve, principal, boxes) = (600, 1_000, 2);
+
+    write_account("short-reserve", "mint", &mint, &spl_token::ID, &mint_data(&vault_admin, COption::None, reserve + boxes * principal));
+    write_account("short-reserve", "token-vault", &token_vault, &spl_token::ID, &token_account_data(&mint, &vault_info_key, reserve));
+
+    let mut vault_info = VaultInfo {
+        compounding: Compounding::Simple,
+        vault_admin,
+        token_mint: mint,
+        token_vault,
+        external_mint: true,
+        ..Default::default()
+    };
+    vault_info.apply_config(&VaultConfig {
+        max_tokens: 1_000_000,
+        annual_rate_bps: 500,
+        max_user_deposit: 100_000,
+        ..Default::default()
+    }).unwrap();
+
+    for index in 0..boxes {
+        let deposit_info = DepositInfo {
+            deposit_index: index,
+            seconds_locked: 100,
+            deposit_lamports: principal,
+            depositor: user,
+            rate_per_second: RAY / 100,
+            compounding: Compounding::Simple,
+            ..Default::default()
+        };
+        vault_info.record_deposit(principal, deposit_info.compute_interest().unwrap()).unwrap();
+
+        let seeds: &[&[u8]] = &[vault_info_key.as_ref(), user.as_ref(), &index.to_le_bytes()];
+        let deposit_info_key = pda(&[&[USER_DEPOSIT_INFO.as_bytes()], seeds].concat());
+        let user_vault = pda(&[&[USER_VAULT_SEED.as_bytes()], seeds].concat());
+        write_account("short-reserve", &format!("deposit-info-{}", index), &deposit_info_key, &crate::ID, &program_account_data(
+            DepositInfo::discriminator(),
+            &deposit_info,
+            8 + std::mem::size_of::<DepositInfo>(),
+        ));
+        write_account("short-reserve", &format!("user-vault-{}", index), &user_vault, &spl_token::ID, &token_account_data(&mint, &vault_info_key, principal));
+    }
+
+    write_account("short-reserve", "vault-info", &vault_info_key, &crate::ID, &program_account_data(
+        VaultInfo::discriminator(),
+        &vault_info,
+        8 + std::mem::size_of::<VaultInfo>(),
+    ));
+}
+
+#[test]
+fn test_base64_pads_partial_chunks() {
+    assert_eq!(base64(b""), "");
+    assert_eq!(base64(b"f"), "Zg==");
+    assert_eq!(base64(b"fo"), "Zm8=");
+    assert_eq!(base64(b"foo"), "Zm9v");
+    assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+}
@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token};
+
+use crate::instructions::initialize::VaultInfo;
+use crate::instructions::set_role::VaultRoles;
+use crate::instructions::propose_admin::{release_freeze_authority, AdminTransferError};
+use crate::events::AdminTransferred;
+use crate::constants::*;
+
+
+pub fn handler(
+    ctx: Context<AcceptAdmin>,
+    info_bump: u8,
+) -> Result<()> {
+
+    // Hand the escrowed freeze authority to the new admin
+    let new_admin = ctx.accounts.new_admin.key();
+    release_freeze_authority(
+        &ctx.accounts.token_program,
+        &ctx.accounts.token_mint,
+        &ctx.accounts.vault_info,
+        info_bump,
+        &new_admin,
+    )?;
+
+    let vault_info = &mut ctx.accounts.vault_info;
+    let previous_admin = vault_info.vault_admin;
+    vault_info.vault_admin = new_admin;
+    vault_info.pending_admin = None;
+
+    // Roles left with the previous admin follow the vault, delegated ones stay put
+    let vault_roles = &mut ctx.accounts.vault_roles;
+    vault_roles.hand_over(previous_admin, new_admin);
+
+    emit!(AdminTransferred {
+        vault_info: vault_info.key(),
+        previous_admin,
+        vault_admin: new_admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(
+    info_bump: u8,
+)]
+/// This AcceptAdmin context is used by the proposed admin to take over the vault.
+///
+/// Requirements
+/// ----------------------
+/// 1) Only the pending admin (see `ProposeAdmin`) can accept.
+/// 2) The freeze authority escrowed at proposal time moves to the new admin.
+/// 3) Roles still held by the previous admin move to the new admin.
+pub struct AcceptAdmin<'info> {
+
+    /// This account holds the metadata for the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = info_bump,
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    /// This account holds the roles of the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_ROLES_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub vault_roles: Account<'info, VaultRoles>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(mut, address=vault_info.token_mint)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// This is the pending admin
+    #[account(
+        constraint = vault_info.pending_admin == Some(new_admin.key()) @ AdminTransferError::NotPendingAdmin,
+    )]
+    pub new_admin: Signer<'info>,
+
+    /// Token Program
+    pub token_program: Program<'info, Token>,
+}
@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token};
+
+use crate::instructions::initialize::VaultInfo;
+use crate::instructions::propose_admin::{release_freeze_authority, AdminTransferError};
+use crate::events::AdminTransferCancelled;
+use crate::constants::*;
+
+
+pub fn handler(
+    ctx: Context<CancelAdminTransfer>,
+    info_bump: u8,
+) -> Result<()> {
+
+    // Ensure there is a transfer to cancel
+    let pending_admin = ctx.accounts.vault_info.pending_admin;
+    require!(pending_admin.is_some(), AdminTransferError::NoPendingAdmin);
+
+    // Return the escrowed freeze authority to the admin
+    release_freeze_authority(
+        &ctx.accounts.token_program,
+        &ctx.accounts.token_mint,
+        &ctx.accounts.vault_info,
+        info_bump,
+        &ctx.accounts.vault_admin.key(),
+    )?;
+
+    let vault_info = &mut ctx.accounts.vault_info;
+    vault_info.pending_admin = None;
+
+    emit!(AdminTransferCancelled {
+        vault_info: vault_info.key(),
+        vault_admin: vault_info.vault_admin,
+        pending_admin: pending_admin.unwrap(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(
+    info_bump: u8,
+)]
+/// This CancelAdminTransfer context is used by the vault admin to withdraw a pending proposal.
+///
+/// Requirements
+/// ----------------------
+/// 1) Only the vault admin can cancel, and only while a transfer is pending.
+/// 2) The freeze authority escrowed at proposal time goes back to the admin.
+pub struct CancelAdminTransfer<'info> {
+
+    /// This account holds the metadata for the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = info_bump,
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(mut, address=vault_info.token_mint)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// This is the vault admin
+    #[account(address=vault_info.vault_admin)]
+    pub vault_admin: Signer<'info>,
+
+    /// Token Program
+    pub token_program: Program<'info, Token>,
+}
@@ -7,7 +7,10 @@ use spl_token;
 use solana_program;
 
 use crate::instructions::initialize::VaultInfo;
+use crate::instructions::set_rate_tiers::RateTiers;
+use crate::events::Deposited;
 use crate::constants::*;
+use crate::math::{compound_interest, compounded_balance_fits, Compounding};
 
 
 pub fn handler(
@@ -16,59 +19,150 @@ pub fn handler(
     deposit_lamports: u64,
     ) -> Result<()> {
 
-    // Ensure user is not depositing more than is allowed
-    require!(deposit_lamports <= MAX_USER_DEPOSIT, DepositError::MaxDepositLimit);
-
-    // Ensure user is depositiing for nonzero time
-    require!(seconds_locked > 0, DepositError::ZeroTimeDeposit);
-
-    // Ensure user is depositing for less than what would break our setup
-    // i.e. interest owed > max tokens in vault
-    require!(seconds_locked < max_time(deposit_lamports), DepositError::BreakingTheBank);
-
-    // Initialize deposit_info account data
-    let deposit_info = &mut ctx.accounts.deposit_info;
-    deposit_info.seconds_locked = seconds_locked;
-    deposit_info.deposit_lamports = deposit_lamports;
-    deposit_info.depositor = ctx.accounts.depositor.key();
-    deposit_info.deposit_time = Clock::get().unwrap().unix_timestamp;
-
-    // Construct instruction using spl_token library
-    let ix = spl_token::instruction::transfer_checked(
-
-        // token_program_id: &Pubkey, 
-        // source_pubkey: &Pubkey, 
-        // mint_pubkey: &Pubkey, 
-        // destination_pubkey: &Pubkey, 
-        // authority_pubkey: &Pubkey, 
-        // signer_pubkeys: &[&Pubkey], 
-        // amount: u64, 
-        // decimals: u8
-
-        &ctx.accounts.token_program.key(),
-        &ctx.accounts.depositor_token_account.key(),
-        &ctx.accounts.token_mint.key(),
-        &ctx.accounts.user_vault.key(),
-        &ctx.accounts.depositor.key(),//&ctx.accounts.vault_admin.key(),
-        &[&ctx.accounts.depositor.key()],
-        deposit_lamports,
-        ctx.accounts.token_mint.decimals,
-    )?;
-
-    // Invoke using solana_program library
-    solana_program::program::invoke(
-        &ix,
-        &[
-            //ctx.accounts.token_program.to_account_info(),
-            ctx.accounts.depositor_token_account.to_account_info(),
-            ctx.accounts.depositor.to_account_info(),
-            ctx.accounts.token_mint.to_account_info(),
-            ctx.accounts.user_vault.to_account_info(),
-            ctx.accounts.vault_admin.to_account_info(),
-        ],
-    )?;
+    // Free-form lock durations can be switched off, leaving the product catalog
+    require!(!ctx.accounts.vault_info.products_only, DepositError::ProductsOnly);
+
+    // Pick the rate of the lock duration's bracket, or the flat vault rate without a table.
+    // Bracket rates are stored per second, already converted under the vault's convention.
+    let rate_tiers = &ctx.accounts.rate_tiers;
+    let tier_rate = if rate_tiers.owner == ctx.program_id && !rate_tiers.data_is_empty() {
+        Account::<RateTiers>::try_from(rate_tiers)?.rate_for(seconds_locked)
+    } else {
+        None
+    };
+
+    let vault_info = &ctx.accounts.vault_info;
+    let compounding = vault_info.compounding;
+    let rate_per_second = tier_rate.unwrap_or(vault_info.rate_per_second);
 
-    Ok(())
+    ctx.accounts.open_box(seconds_locked, rate_per_second, compounding, deposit_lamports, None)
+}
+
+impl<'info> Deposit<'info> {
+
+    /// Opens the depositor's next deposit box with the given terms, moving `deposit_lamports` into it.
+    /// `product` is recorded in the box when it is opened through a product (see `deposit_product`).
+    pub fn open_box(
+        &mut self,
+        seconds_locked: u64,
+        rate_per_second: u128,
+        compounding: Compounding,
+        deposit_lamports: u64,
+        product: Option<Pubkey>,
+    ) -> Result<()> {
+
+        // Ensure deposits are open
+        let vault_info = &self.vault_info;
+        require!(!vault_info.deposits_paused && !vault_info.emergency_mode, DepositError::DepositsPaused);
+
+        // Ensure user is not depositing more than is allowed
+        require!(deposit_lamports <= vault_info.max_user_deposit, DepositError::MaxDepositLimit);
+
+        // Ensure user is depositiing for nonzero time
+        require!(seconds_locked > 0, DepositError::ZeroTimeDeposit);
+
+        // Ensure user is depositing for less than what would break our setup
+        // i.e. interest owed > max tokens in vault
+        require!(
+            compounded_balance_fits(
+                deposit_lamports,
+                rate_per_second,
+                seconds_locked,
+                vault_info.max_tokens,
+                compounding,
+            ),
+            DepositError::BreakingTheBank
+        );
+
+        // Matured boxes keep earning the vault's post-maturity quote under the box's own convention
+        let post_maturity_rate_per_second = vault_info.post_maturity_rate_for(compounding);
+        require!(post_maturity_rate_per_second.is_some(), DepositError::BreakingTheBank);
+
+        // Claim the next deposit index for this user
+        let user_profile = &mut self.user_profile;
+        user_profile.owner = self.depositor.key();
+        let deposit_index = user_profile.deposit_count;
+        user_profile.deposit_count = deposit_index.checked_add(1).unwrap();
+
+        // Initialize deposit_info account data
+        let deposit_info = &mut self.deposit_info;
+        deposit_info.deposit_index = deposit_index;
+        deposit_info.seconds_locked = seconds_locked;
+        deposit_info.deposit_lamports = deposit_lamports;
+        deposit_info.rate_per_second = rate_per_second;
+        deposit_info.compounding = compounding;
+        deposit_info.early_withdrawal_penalty_bps = vault_info.early_withdrawal_penalty_bps;
+        deposit_info.post_maturity_rate_per_second = post_maturity_rate_per_second.unwrap();
+        deposit_info.max_post_maturity_seconds = vault_info.max_post_maturity_seconds;
+        deposit_info.product = product;
+        deposit_info.depositor = self.depositor.key();
+        deposit_info.deposit_time = Clock::get().unwrap().unix_timestamp;
+
+        // Ensure the reserve can cover the most this deposit can earn on top of everything already promised
+        let promised_interest = deposit_info.compute_interest();
+        require!(promised_interest.is_some(), DepositError::BreakingTheBank);
+        let vault_info = &mut self.vault_info;
+        require!(
+            vault_info.record_deposit(deposit_lamports, promised_interest.unwrap()).is_some(),
+            DepositError::BreakingTheBank
+        );
+        require!(
+            vault_info.is_solvent(self.token_vault.amount),
+            DepositError::ReserveCannotCoverPromises
+        );
+
+        // Construct instruction using spl_token library
+        let ix = spl_token::instruction::transfer_checked(
+
+            // token_program_id: &Pubkey, 
+            // source_pubkey: &Pubkey, 
+            // mint_pubkey: &Pubkey, 
+            // destination_pubkey: &Pubkey, 
+            // authority_pubkey: &Pubkey, 
+            // signer_pubkeys: &[&Pubkey], 
+            // amount: u64, 
+            // decimals: u8
+
+            &self.token_program.key(),
+            &self.depositor_token_account.key(),
+            &self.token_mint.key(),
+            &self.user_vault.key(),
+            &self.depositor.key(),//&self.vault_admin.key(),
+            &[&self.depositor.key()],
+            deposit_lamports,
+            self.token_mint.decimals,
+        )?;
+
+        // Invoke using solana_program library
+        solana_program::program::invoke(
+            &ix,
+            &[
+                //self.token_program.to_account_info(),
+                self.depositor_token_account.to_account_info(),
+                self.depositor.to_account_info(),
+                self.token_mint.to_account_info(),
+                self.user_vault.to_account_info(),
+                self.vault_admin.to_account_info(),
+            ],
+        )?;
+
+        let deposit_info = &self.deposit_info;
+        emit!(Deposited {
+            vault_info: self.vault_info.key(),
+            depositor: deposit_info.depositor,
+            deposit_info: deposit_info.key(),
+            user_vault: self.user_vault.key(),
+            deposit_index,
+            deposit_lamports,
+            seconds_locked,
+            rate_per_second,
+            promised_interest: promised_interest.unwrap(),
+            product,
+            timestamp: deposit_info.deposit_time,
+        });
+
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
@@ -82,17 +176,34 @@ pub fn handler(
 /// 
 /// Requirements
 /// ----------------------
-/// 1) It should hold up to 10,000,000 of a custom SPL token.
+/// 1) It should hold up to vault_info.max_tokens of a custom SPL token.
 /// 2) Only some admin should have the authority to mint + deposit this SPL token.
 pub struct Deposit<'info> {
 
-    /// This account holds the metadata for the vault
+    /// This account keeps track of how many deposit boxes the user has opened
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        seeds = [
+            USER_PROFILE_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+            &depositor.key.to_bytes(),
+        ],
+        bump,
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+
+    /// This account holds the metadata for the deposit box
+    /// (sized explicitly, since its default serialization has no room for a product)
     #[account(
         init,
         payer = depositor,
+        space = 8 + std::mem::size_of::<DepositInfo>(),
         seeds = [
             USER_DEPOSIT_INFO.as_bytes(), 
+            &vault_info.key().to_bytes(),
             &depositor.key.to_bytes(),
+            &user_profile.deposit_count.to_le_bytes(),
         ],
         bump,
     )]
@@ -100,18 +211,39 @@ pub struct Deposit<'info> {
 
     /// This account holds the metadata for the vault
     #[account(
-        seeds = [VAULT_INFO_SEED.as_bytes()],
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
         bump = vault_info_bump
     )]
     pub vault_info: Account<'info, VaultInfo>,
 
+    /// This token account is PDA which serves as the reserve for the SPL token
+    #[account(address=vault_info.token_vault)]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    /// This account holds the rate table of the vault, if the risk manager published one
+    /// CHECK: This is fine because it is only read when it is a `RateTiers` account owned by our program
+    #[account(
+        seeds = [
+            RATE_TIERS_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub rate_tiers: UncheckedAccount<'info>,
+
     /// This token account serves as the account which holds the SPL token
     #[account(
         init,
         payer = depositor,
         seeds = [
             USER_VAULT_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
             &depositor.key.to_bytes(),
+            &user_profile.deposit_count.to_le_bytes(),
         ],
         bump,
         token::mint = token_mint,
@@ -158,9 +290,25 @@ pub struct Deposit<'info> {
 
 #[account]
 #[derive(Default)]
-/// This struct holds all of the metadata for the vault
+/// This struct keeps a per-user counter of deposit boxes
+pub struct UserProfile {
+
+    /// The user owning the deposit boxes
+    pub owner: Pubkey,
+
+    /// Number of deposit boxes opened so far (also the index of the next one)
+    pub deposit_count: u64,
+
+}
+
+#[account]
+#[derive(Default)]
+/// This struct holds all of the metadata for a single deposit box
 pub struct DepositInfo {
 
+    /// Index of this deposit box among the depositor's boxes
+    pub deposit_index: u64,
+
     /// Time in seconds deposited
     pub seconds_locked: u64,
 
@@ -172,6 +320,27 @@ pub struct DepositInfo {
 
     /// Time deposited
     pub deposit_time: i64,
+
+    /// Per-second rate locked in at deposit time (in `RAY`)
+    pub rate_per_second: u128,
+
+    /// Compounding convention locked in at deposit time
+    pub compounding: Compounding,
+
+    /// Early withdrawal penalty locked in at deposit time (in bps)
+    pub early_withdrawal_penalty_bps: u64,
+
+    /// The product this box was opened through, if any
+    pub product: Option<Pubkey>,
+
+    /// Per-second rate the matured balance keeps earning, locked in at deposit time (in `RAY`)
+    pub post_maturity_rate_per_second: u128,
+
+    /// How long after maturity the balance keeps earning, locked in at deposit time (in seconds)
+    pub max_post_maturity_seconds: u64,
+
+    /// Interest credited into `deposit_lamports` by top-ups, which an early withdrawal forfeits
+    pub credited_interest: u64,
     
 }
 
@@ -185,33 +354,244 @@ impl DepositInfo{
         self.get_elapsed() as u64 >= self.seconds_locked
     }
     
-    pub fn compute_interest(&self) -> u64 {
-        (self.deposit_lamports as f64 
-            * ((1.0 + INTEREST_RATE_TENTHBPS as f64/100000.0).powf(self.seconds_locked as f64) - 1.0))
-             as u64
+    /// Interest the box earns if left for its whole post-maturity window, the most it can be owed.
+    /// This is the liability the vault records for the box while it is open.
+    pub fn compute_interest(&self) -> Option<u64> {
+        self.interest_after(self.seconds_locked.saturating_add(self.max_post_maturity_seconds))
+    }
+
+    /// Interest earned `elapsed` seconds after the box was opened (rounded down).
+    ///
+    /// Up to maturity the principal earns the box's rate. After it, the matured balance
+    /// keeps compounding at the post-maturity rate, for at most `max_post_maturity_seconds`.
+    pub fn interest_after(&self, elapsed: u64) -> Option<u64> {
+        let interest = self.interest_over(elapsed.min(self.seconds_locked))?;
+        let post_maturity_seconds = elapsed
+            .saturating_sub(self.seconds_locked)
+            .min(self.max_post_maturity_seconds);
+        if post_maturity_seconds == 0 {
+            return Some(interest);
+        }
+
+        let post_maturity_interest = compound_interest(
+            self.deposit_lamports.checked_add(interest)?,
+            self.post_maturity_rate_per_second,
+            post_maturity_seconds,
+            self.compounding,
+        )?;
+        interest.checked_add(post_maturity_interest)
+    }
+
+    /// Interest earned by the current principal over `seconds` (rounded down),
+    /// at the rate and compounding convention locked in when the box was opened
+    pub fn interest_over(&self, seconds: u64) -> Option<u64> {
+        compound_interest(
+            self.deposit_lamports,
+            self.rate_per_second,
+            seconds,
+            self.compounding,
+        )
+    }
+
+    /// Principal forfeited when withdrawing before maturity (rounded up, so it never goes against the bank).
+    /// It is charged on the deposited principal, interest credited by top-ups is forfeited in full on top.
+    pub fn early_withdrawal_penalty(&self) -> u64 {
+        let principal = self.deposit_lamports.saturating_sub(self.credited_interest);
+        let penalty = (principal as u128 * self.early_withdrawal_penalty_bps as u128
+            + 9_999) / 10_000;
+        penalty.min(principal as u128) as u64
+    }
+
+    /// Top-up policy: the lock restarts with a blended principal.
+    ///
+    /// Interest accrued so far (see `interest_after`) is credited into the box (and recorded in
+    /// `credited_interest`, so breaking the box early cannot turn it into principal),
+    /// the top-up amount is added on top, and the full lock period restarts at `now`.
+    /// Returns the accrued interest, which must be moved from the reserve into the box.
+    pub fn apply_top_up(&mut self, amount: u64, now: i64) -> Option<u64> {
+        let elapsed = now.checked_sub(self.deposit_time)?.max(0) as u64;
+        let accrued = self.interest_after(elapsed)?;
+        self.deposit_lamports = self.deposit_lamports
+            .checked_add(accrued)?
+            .checked_add(amount)?;
+        self.credited_interest = self.credited_interest.checked_add(accrued)?;
+        self.deposit_time = now;
+        Some(accrued)
     }
 }
 
 
 #[error_code]
 pub enum DepositError {
-    #[msg(format!("Attempting to deposit over limit of {} tokens", MAX_USER_DEPOSIT))]
+    #[msg("Attempting to deposit over the vault's deposit limit")]
     MaxDepositLimit,
     #[msg("Attempting to deposit for zero time")]
     ZeroTimeDeposit,
     #[msg("Attempting to deposit for an amount of time that would break the bank")]
     BreakingTheBank,
+    #[msg("The reserve cannot cover the interest promised to all depositors")]
+    ReserveCannotCoverPromises,
+    #[msg("Deposits are paused")]
+    DepositsPaused,
+    #[msg("This vault only accepts deposits into its products")]
+    ProductsOnly,
 }
 
-fn max_time(
-    deposit_lamports: u64,
-) -> u64 {
-    // this solves deposit_lamports * (1 + interest)^seconds = max_vault_balance
-    ((MAX_RESERVE_TOKEN_AMOUNT as f64/deposit_lamports as f64).log(10.0)
-    /(1.0 +INTEREST_RATE_TENTHBPS as f64/ 100000.0).log(10.0)) as u64
+#[cfg(test)]
+use crate::math::tenthbps_to_ray;
+
+#[test]
+fn test_top_up_restarts_lock_with_blended_principal(){
+    let mut deposit_info = DepositInfo {
+        seconds_locked: 100,
+        deposit_lamports: 100_000,
+        deposit_time: 1_000,
+        rate_per_second: tenthbps_to_ray(35),
+        ..Default::default()
+    };
+    let accrued_50 = deposit_info.interest_over(50).unwrap();
+
+    // Halfway through the lock, accrued interest is credited and the lock restarts
+    assert_eq!(deposit_info.apply_top_up(10_000, 1_050), Some(accrued_50));
+    assert_eq!(deposit_info.deposit_lamports, 110_000 + accrued_50);
+    assert_eq!(deposit_info.deposit_time, 1_050);
+    assert_eq!(deposit_info.seconds_locked, 100);
 }
 
 #[test]
-fn test_max_time(){
-    assert_eq!(max_time(100_000), 13_159)
-}
\ No newline at end of file
+fn test_top_up_accrual_is_capped_at_lock_period(){
+    let mut deposit_info = DepositInfo {
+        seconds_locked: 100,
+        deposit_lamports: 100_000,
+        deposit_time: 1_000,
+        rate_per_second: tenthbps_to_ray(35),
+        ..Default::default()
+    };
+    let full_interest = deposit_info.compute_interest().unwrap();
+
+    // Without post-maturity accrual, topping up long after maturity only credits the interest of the lock period
+    assert_eq!(deposit_info.apply_top_up(1, 5_000), Some(full_interest));
+    assert_eq!(deposit_info.deposit_lamports, 100_001 + full_interest);
+}
+
+#[test]
+fn test_interest_uses_terms_locked_in_at_deposit(){
+    let deposit_info = DepositInfo {
+        seconds_locked: 100,
+        deposit_lamports: 100_000,
+        rate_per_second: tenthbps_to_ray(35),
+        ..Default::default()
+    };
+    let faster = DepositInfo { rate_per_second: tenthbps_to_ray(70), ..deposit_info };
+
+    // The payout only depends on the box's own rate and convention, not the vault's current ones
+    assert_eq!(
+        deposit_info.compute_interest(),
+        compound_interest(100_000, tenthbps_to_ray(35), 100, Compounding::PerSecond)
+    );
+    assert!(faster.compute_interest() > deposit_info.compute_interest());
+}
+
+#[test]
+fn test_post_maturity_accrual_is_capped(){
+    let deposit_info = DepositInfo {
+        seconds_locked: 100,
+        deposit_lamports: 100_000,
+        rate_per_second: tenthbps_to_ray(35),
+        post_maturity_rate_per_second: tenthbps_to_ray(10),
+        max_post_maturity_seconds: 50,
+        ..Default::default()
+    };
+    let at_maturity = deposit_info.interest_over(100).unwrap();
+
+    // Before and at maturity only the box's own rate applies
+    assert_eq!(deposit_info.interest_after(40), deposit_info.interest_over(40));
+    assert_eq!(deposit_info.interest_after(100), Some(at_maturity));
+
+    // After it, the matured balance keeps compounding at the post-maturity rate, up to the cap
+    let post_maturity = |seconds| compound_interest(100_000 + at_maturity, tenthbps_to_ray(10), seconds, Compounding::PerSecond).unwrap();
+    assert_eq!(deposit_info.interest_after(120), Some(at_maturity + post_maturity(20)));
+    assert_eq!(deposit_info.interest_after(150), Some(at_maturity + post_maturity(50)));
+    assert_eq!(deposit_info.interest_after(10_000), Some(at_maturity + post_maturity(50)));
+
+    // The liability recorded for the box is the most it can earn
+    assert_eq!(deposit_info.compute_interest(), Some(at_maturity + post_maturity(50)));
+    assert!(deposit_info.compute_interest() > DepositInfo { max_post_maturity_seconds: 0, ..deposit_info }.compute_interest());
+}
+
+#[cfg(test)]
+use crate::instructions::initialize::VaultConfig;
+#[cfg(test)]
+use crate::math::{RateBasis, SECONDS_PER_YEAR};
+
+#[test]
+fn test_post_maturity_rate_follows_the_box_compounding(){
+    // A simple interest vault quoting a 10% APY after maturity
+    let mut vault_info = VaultInfo { compounding: Compounding::Simple, ..Default::default() };
+    vault_info.apply_config(&VaultConfig {
+        max_tokens: 10_000_000,
+        annual_rate_bps: 500,
+        rate_basis: RateBasis::Apy,
+        post_maturity_rate_bps: 1_000,
+        max_post_maturity_seconds: SECONDS_PER_YEAR,
+        max_user_deposit: 100_000,
+        ..Default::default()
+    }).unwrap();
+    assert_eq!(vault_info.post_maturity_rate_for(Compounding::Simple), Some(vault_info.post_maturity_rate_per_second));
+
+    // A box opened through a product compounding every second gets the rate converted under its own convention
+    let post_maturity_rate_per_second = vault_info.post_maturity_rate_for(Compounding::PerSecond).unwrap();
+    assert!(post_maturity_rate_per_second < vault_info.post_maturity_rate_per_second);
+    let deposit_info = DepositInfo {
+        seconds_locked: 1,
+        deposit_lamports: 1_000_000_000,
+        compounding: Compounding::PerSecond,
+        post_maturity_rate_per_second,
+        max_post_maturity_seconds: SECONDS_PER_YEAR,
+        ..Default::default()
+    };
+
+    // A year past maturity it has earned the quoted yield and no more
+    let earned = deposit_info.compute_interest().unwrap();
+    assert!(earned <= 100_000_000 && earned > 99_999_000);
+
+    // The vault's simple interest rate compounded every second would overshoot the quote
+    let copied = DepositInfo { post_maturity_rate_per_second: vault_info.post_maturity_rate_per_second, ..deposit_info };
+    assert!(copied.compute_interest().unwrap() > 105_000_000);
+}
+
+#[test]
+fn test_top_up_interest_is_tracked_for_early_withdrawal(){
+    let mut deposit_info = DepositInfo {
+        seconds_locked: 100,
+        deposit_lamports: 100_000,
+        deposit_time: 1_000,
+        rate_per_second: tenthbps_to_ray(35),
+        early_withdrawal_penalty_bps: 500,
+        ..Default::default()
+    };
+    let accrued_50 = deposit_info.apply_top_up(1, 1_050).unwrap();
+    assert!(accrued_50 > 0);
+    assert_eq!(deposit_info.credited_interest, accrued_50);
+
+    // The penalty is charged on the 100_001 deposited, not on the credited interest
+    assert_eq!(deposit_info.early_withdrawal_penalty(), 5_001);
+
+    // Credited interest keeps adding up over successive top-ups
+    let accrued_later = deposit_info.apply_top_up(1, 1_060).unwrap();
+    assert_eq!(deposit_info.credited_interest, accrued_50 + accrued_later);
+}
+
+#[test]
+fn test_early_withdrawal_penalty_rounds_up(){
+    let deposit_info = DepositInfo {
+        deposit_lamports: 100_001,
+        early_withdrawal_penalty_bps: 500,
+        ..Default::default()
+    };
+
+    // 5% of 100_001 is 5_000.05, which the bank keeps in full
+    assert_eq!(deposit_info.early_withdrawal_penalty(), 5_001);
+    assert_eq!(DepositInfo { early_withdrawal_penalty_bps: 0, ..deposit_info }.early_withdrawal_penalty(), 0);
+    assert_eq!(DepositInfo { early_withdrawal_penalty_bps: 10_000, ..deposit_info }.early_withdrawal_penalty(), 100_001);
+}
@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use crate::instructions::deposit::*;
+use crate::instructions::set_product::{Product, ProductError};
+use crate::constants::*;
+
+
+pub fn handler(
+    ctx: Context<DepositProduct>,
+    deposit_lamports: u64,
+) -> Result<()> {
+
+    // Ensure the product takes this deposit
+    let product = &mut ctx.accounts.product;
+    require!(product.open, ProductError::ProductClosed);
+    require!(
+        deposit_lamports >= product.min_deposit && deposit_lamports <= product.max_deposit,
+        ProductError::OutsideDepositRange
+    );
+    require!(product.record_deposit(deposit_lamports).is_some(), ProductError::CapacityReached);
+
+    // Open the box with the product's term and rate
+    let (seconds_locked, rate_per_second, compounding) = (product.seconds_locked, product.rate_per_second, product.compounding);
+    let product_key = product.key();
+    ctx.accounts.deposit.open_box(seconds_locked, rate_per_second, compounding, deposit_lamports, Some(product_key))
+}
+
+/// Releases a closing box from the product it was opened through, if any.
+///
+/// The product account must then be the first of `remaining_accounts`.
+pub fn release_from_product(
+    program_id: &Pubkey,
+    remaining_accounts: &[AccountInfo],
+    deposit_info: &DepositInfo,
+) -> Result<()> {
+
+    let product_key = match deposit_info.product {
+        Some(product_key) => product_key,
+        None => return Ok(()),
+    };
+    require!(
+        remaining_accounts.first().map(|account| account.key()) == Some(product_key),
+        ProductError::WrongProduct
+    );
+
+    let mut product = Account::<Product>::try_from(&remaining_accounts[0])?;
+    require!(product.release_deposit(deposit_info.deposit_lamports).is_some(), ProductError::WrongProduct);
+    product.exit(program_id)
+}
+
+#[derive(Accounts)]
+#[instruction(
+    reserve_bump: u8,
+    vault_info_bump: u8,
+    deposit_info_bump: u8,
+    user_vault_bump: u8,
+    product_id: u64,
+)]
+/// This DepositProduct context is used to open a deposit box through a product.
+///
+/// Requirements
+/// ----------------------
+/// 1) The product must be open, and the deposit within its range and remaining capacity.
+/// 2) The box gets the product's term, rate and compounding convention, on top of the checks of a regular deposit.
+/// 3) Closing the box requires the product account as the first remaining account.
+pub struct DepositProduct<'info> {
+
+    /// The accounts of a regular deposit
+    pub deposit: Deposit<'info>,
+
+    /// This account is a PDA that holds the product
+    #[account(
+        mut,
+        seeds = [
+            PRODUCT_SEED.as_bytes(),
+            &deposit.vault_info.key().to_bytes(),
+            &product_id.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub product: Account<'info, Product>,
+}
@@ -0,0 +1,212 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{TokenAccount, Mint, Token}
+};
+use spl_token;
+use solana_program;
+
+use crate::instructions::initialize::VaultInfo;
+use crate::instructions::deposit::DepositInfo;
+use crate::instructions::deposit_product::release_from_product;
+use crate::events::EmergencyWithdrawn;
+use crate::constants::*;
+
+
+pub fn handler(
+    ctx: Context<EmergencyWithdraw>,
+    vault_info_bump: u8,
+) -> Result<()> {
+
+    // Grab deposit info
+    let deposit_info = &ctx.accounts.deposit_info;
+
+    // The vault_info PDA is namespaced by the mint
+    let mint_key = ctx.accounts.token_mint.key();
+
+    // Only available while interest payouts are frozen. This deliberately
+    // ignores the lockout and withdrawals_paused, so principal is always recoverable.
+    require!(ctx.accounts.vault_info.emergency_mode, EmergencyWithdrawError::NotInEmergencyMode);
+
+    // This box no longer counts towards the vault's liabilities
+    let promised_interest = deposit_info.compute_interest();
+    require!(promised_interest.is_some(), EmergencyWithdrawError::InterestOverflow);
+    require!(
+        ctx.accounts.vault_info.release_deposit(deposit_info.deposit_lamports, promised_interest.unwrap()).is_some(),
+        EmergencyWithdrawError::LiabilitiesUnderflow
+    );
+    // ...and from its product, if it was opened through one
+    release_from_product(ctx.program_id, ctx.remaining_accounts, deposit_info)?;
+
+    // First, return the principal to the user
+    let ix = spl_token::instruction::transfer_checked(
+
+        // token_program_id: &Pubkey,
+        // source_pubkey: &Pubkey,
+        // mint_pubkey: &Pubkey,
+        // destination_pubkey: &Pubkey,
+        // authority_pubkey: &Pubkey,
+        // signer_pubkeys: &[&Pubkey],
+        // amount: u64,
+        // decimals: u8
+
+        &ctx.accounts.token_program.key(),
+        &ctx.accounts.user_vault.key(),
+        &ctx.accounts.token_mint.key(),
+        &ctx.accounts.depositor_token_account.key(),
+        &ctx.accounts.vault_info.key(),
+        &[&ctx.accounts.vault_info.key()],
+        deposit_info.deposit_lamports,
+        ctx.accounts.token_mint.decimals,
+    )?;
+
+    // Invoke using solana_program library
+    solana_program::program::invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.user_vault.to_account_info(),
+            ctx.accounts.vault_info.to_account_info(),
+            ctx.accounts.token_mint.to_account_info(),
+            ctx.accounts.depositor_token_account.to_account_info(),
+        ],
+        &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[vault_info_bump]]]
+    )?;
+
+    // Second, close the (now empty) user vault and return its rent to the user
+    let ix = spl_token::instruction::close_account(
+
+        // token_program_id: &Pubkey,
+        // account_pubkey: &Pubkey,
+        // destination_pubkey: &Pubkey,
+        // owner_pubkey: &Pubkey,
+        // signer_pubkeys: &[&Pubkey]
+
+        &ctx.accounts.token_program.key(),
+        &ctx.accounts.user_vault.key(),
+        &ctx.accounts.depositor.key(),
+        &ctx.accounts.vault_info.key(),
+        &[&ctx.accounts.vault_info.key()],
+    )?;
+
+    // Invoke using solana_program library
+    solana_program::program::invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.user_vault.to_account_info(),
+            ctx.accounts.depositor.to_account_info(),
+            ctx.accounts.vault_info.to_account_info(),
+        ],
+        &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[vault_info_bump]]]
+    )?;
+
+    emit!(EmergencyWithdrawn {
+        vault_info: ctx.accounts.vault_info.key(),
+        depositor: ctx.accounts.depositor.key(),
+        deposit_info: deposit_info.key(),
+        deposit_index: deposit_info.deposit_index,
+        deposit_lamports: deposit_info.deposit_lamports,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    // deposit_info is closed to the depositor by its `close` constraint
+
+    Ok(())
+}
+
+
+
+#[derive(Accounts)]
+#[instruction(
+    vault_info_bump: u8,
+    deposit_info_bump: u8,
+    user_vault_bump: u8,
+    deposit_index: u64,
+)]
+/// This EmergencyWithdraw context is used to recover the principal of a deposit box in emergency mode.
+///
+/// Requirements
+/// ----------------------
+/// 1) The vault must be in emergency mode (see `SetPause`).
+/// 2) The full principal is returned at any time, without interest or penalty.
+pub struct EmergencyWithdraw<'info> {
+
+    /// This account holds the metadata for the deposit box being withdrawn
+    /// (closed on withdraw, rent goes back to the depositor)
+    #[account(
+        mut,
+        close = depositor,
+        seeds = [
+            USER_DEPOSIT_INFO.as_bytes(),
+            &vault_info.key().to_bytes(),
+            &depositor.key.to_bytes(),
+            &deposit_index.to_le_bytes(),
+        ],
+        bump = deposit_info_bump,
+    )]
+    pub deposit_info: Box<Account<'info, DepositInfo>>,
+
+    /// This account holds the metadata for the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = vault_info_bump
+    )]
+    pub vault_info: Box<Account<'info, VaultInfo>>,
+
+    /// This token account serves as the account which holds the SPL token
+    /// (closed on withdraw, rent goes back to the depositor)
+    #[account(
+        mut,
+        seeds = [
+            USER_VAULT_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+            &depositor.key.to_bytes(),
+            &deposit_index.to_le_bytes(),
+        ],
+        bump = user_vault_bump,
+    )]
+    pub user_vault: Box<Account<'info, TokenAccount>>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(address=vault_info.token_mint)]
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    /// This account is the user/depositor
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// This account is the user's SPL token account
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        associated_token::mint = token_mint,
+        associated_token::authority = depositor,
+    )]
+    pub depositor_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// System Program
+    pub system_program: Program<'info, System>,
+
+    /// Token Program
+    pub token_program: Program<'info, Token>,
+
+    /// Token Program
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// Rent Program
+    pub rent: Sysvar<'info, Rent>,
+}
+
+
+#[error_code]
+pub enum EmergencyWithdrawError {
+    #[msg("Emergency withdrawals are only open in emergency mode")]
+    NotInEmergencyMode,
+    #[msg("Interest owed does not fit in a token amount")]
+    InterestOverflow,
+    #[msg("Vault liabilities are smaller than this deposit box")]
+    LiabilitiesUnderflow,
+}
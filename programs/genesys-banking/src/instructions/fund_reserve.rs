@@ -0,0 +1,144 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{TokenAccount, Mint, Token};
+use spl_token;
+use solana_program;
+
+use crate::instructions::initialize::VaultInfo;
+use crate::instructions::withdraw::settle_queued_claims;
+use crate::events::ReserveFunded;
+use crate::constants::*;
+
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, FundReserve<'info>>,
+    info_bump: u8,
+    amount: u64,
+) -> Result<()> {
+
+    // Ensure funder is contributing a nonzero amount
+    require!(amount > 0, FundError::ZeroFunding);
+
+    // Record the contribution
+    let vault_info = &mut ctx.accounts.vault_info;
+    let total_funded = vault_info.total_funded.checked_add(amount);
+    require!(total_funded.is_some(), FundError::FundingOverflow);
+    vault_info.total_funded = total_funded.unwrap();
+
+    // Construct instruction using spl_token library
+    let ix = spl_token::instruction::transfer_checked(
+
+        // token_program_id: &Pubkey,
+        // source_pubkey: &Pubkey,
+        // mint_pubkey: &Pubkey,
+        // destination_pubkey: &Pubkey,
+        // authority_pubkey: &Pubkey,
+        // signer_pubkeys: &[&Pubkey],
+        // amount: u64,
+        // decimals: u8
+
+        &ctx.accounts.token_program.key(),
+        &ctx.accounts.funder_token_account.key(),
+        &ctx.accounts.token_mint.key(),
+        &ctx.accounts.token_vault.key(),
+        &ctx.accounts.funder.key(),
+        &[&ctx.accounts.funder.key()],
+        amount,
+        ctx.accounts.token_mint.decimals,
+    )?;
+
+    // Invoke using solana_program library
+    solana_program::program::invoke(
+        &ix,
+        &[
+            ctx.accounts.funder_token_account.to_account_info(),
+            ctx.accounts.funder.to_account_info(),
+            ctx.accounts.token_mint.to_account_info(),
+            ctx.accounts.token_vault.to_account_info(),
+        ],
+    )?;
+
+    // Pay queued interest out of the funded reserve (see `settle_queued_claims`)
+    ctx.accounts.token_vault.reload()?;
+    let settled_interest = settle_queued_claims(
+        ctx.program_id,
+        ctx.remaining_accounts,
+        &mut ctx.accounts.vault_info,
+        &mut ctx.accounts.token_vault,
+        &ctx.accounts.token_mint,
+        &ctx.accounts.token_program,
+        info_bump,
+    )?;
+
+    emit!(ReserveFunded {
+        vault_info: ctx.accounts.vault_info.key(),
+        token_vault: ctx.accounts.token_vault.key(),
+        funder: ctx.accounts.funder.key(),
+        amount,
+        total_funded: ctx.accounts.vault_info.total_funded,
+        settled_interest,
+        reserve_balance: ctx.accounts.token_vault.amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(
+    info_bump: u8,
+)]
+/// This FundReserve context is used to transfer tokens into the reserve.
+///
+/// Requirements
+/// ----------------------
+/// 1) Anyone can fund the reserve (admin, treasury, DAO), from any token account they own.
+/// 2) Works for every vault, including those over external mints.
+/// 3) Queued interest is settled when the oldest `QueuedClaim`s, their token accounts and claimants are passed as remaining accounts.
+pub struct FundReserve<'info> {
+
+    /// This account holds the metadata for the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = info_bump,
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    /// This token account is PDA which serves as the reserve for the SPL token
+    #[account(
+        mut,
+        address=vault_info.token_vault
+    )]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(address=vault_info.token_mint)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// This account is the funder
+    pub funder: Signer<'info>,
+
+    /// This is the funder's SPL token account
+    #[account(
+        mut,
+        constraint = funder_token_account.mint == token_mint.key() @ FundError::WrongMint,
+    )]
+    pub funder_token_account: Account<'info, TokenAccount>,
+
+    /// Token Program
+    pub token_program: Program<'info, Token>,
+}
+
+
+#[error_code]
+pub enum FundError {
+    #[msg("Attempting to fund the reserve with zero tokens")]
+    ZeroFunding,
+    #[msg("Total funding does not fit in a token amount")]
+    FundingOverflow,
+    #[msg("Funding token account is not for the vault's mint")]
+    WrongMint,
+}
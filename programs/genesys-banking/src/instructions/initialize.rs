@@ -1,19 +1,45 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
 use anchor_spl::token::{TokenAccount, Mint, Token};
+use spl_token::{self, instruction::AuthorityType};
+use solana_program;
+use crate::instructions::update_vault_config::ConfigError;
+use crate::instructions::set_role::VaultRoles;
+use crate::events::VaultInitialized;
 use crate::constants::*;
+use crate::math::{annual_bps_to_per_second_ray, to_base_units, Compounding, RateBasis};
 
 
 
 pub fn handler(
     ctx: Context<InitializeVault>,
+    config: VaultConfig,
+    external_mint: bool,
     ) -> Result<()> {
 
+    // Hand mint authority over to the vault_info PDA so that reserve
+    // minting can only happen through program logic. External mints
+    // keep their authority, and their reserve is funded by transfers.
+    if !external_mint {
+        hand_mint_authority_to_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.token_mint,
+            &ctx.accounts.vault_admin,
+            &ctx.accounts.vault_info.key(),
+        )?;
+    }
+
+    // Every role starts with the admin
+    ctx.accounts.vault_roles.init(ctx.accounts.vault_info.key(), ctx.accounts.vault_admin.key());
+
     // Grab vault_info from accounts
     let vault_info = &mut ctx.accounts.vault_info;
 
-    // Set reserve maximum and interest rate
-    vault_info.max_tokens = MAX_RESERVE_TOKEN_AMOUNT;
-    vault_info.interest_rate = INTEREST_RATE_TENTHBPS;
+    // Set reserve maximum, deposit limit and reserve buffer (given in whole tokens),
+    // annual interest rate and early withdrawal penalty
+    vault_info.decimals = ctx.accounts.token_mint.decimals;
+    vault_info.compounding = Compounding::PerSecond;
+    vault_info.apply_config(&config)?;
 
     // Set vault authority
     vault_info.vault_admin = ctx.accounts.vault_admin.key();
@@ -23,35 +49,115 @@ pub fn handler(
 
     // Set token mint
     vault_info.token_mint = ctx.accounts.token_mint.key();
+    vault_info.external_mint = external_mint;
+
+    emit!(VaultInitialized {
+        vault_info: vault_info.key(),
+        token_mint: vault_info.token_mint,
+        token_vault: vault_info.token_vault,
+        vault_admin: vault_info.vault_admin,
+        max_tokens: vault_info.max_tokens,
+        annual_rate_bps: vault_info.annual_rate_bps,
+        rate_basis: vault_info.rate_basis,
+        rate_per_second: vault_info.rate_per_second,
+        post_maturity_rate_bps: vault_info.post_maturity_rate_bps,
+        max_post_maturity_seconds: vault_info.max_post_maturity_seconds,
+        max_user_deposit: vault_info.max_user_deposit,
+        early_withdrawal_penalty_bps: vault_info.early_withdrawal_penalty_bps,
+        reserve_buffer: vault_info.reserve_buffer,
+        products_only: vault_info.products_only,
+        external_mint,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Moves the mint authority of `token_mint` from `vault_admin` to the vault_info PDA.
+pub fn hand_mint_authority_to_vault<'info>(
+    token_program: &Program<'info, Token>,
+    token_mint: &Account<'info, Mint>,
+    vault_admin: &Signer<'info>,
+    vault_info: &Pubkey,
+) -> Result<()> {
+
+    // Construct instruction using spl_token library
+    let ix = spl_token::instruction::set_authority(
+
+        // token_program_id: &Pubkey,
+        // owned_pubkey: &Pubkey,
+        // new_authority_pubkey: Option<&Pubkey>,
+        // authority_type: AuthorityType,
+        // owner_pubkey: &Pubkey,
+        // signer_pubkeys: &[&Pubkey]
+
+        &token_program.key(),
+        &token_mint.key(),
+        Some(vault_info),
+        AuthorityType::MintTokens,
+        &vault_admin.key(),
+        &[&vault_admin.key()],
+    )?;
+
+    // Invoke using solana_program library
+    solana_program::program::invoke(
+        &ix,
+        &[
+            token_mint.to_account_info(),
+            vault_admin.to_account_info(),
+        ],
+    )?;
 
     Ok(())
 }
 
 #[derive(Accounts)]
+#[instruction(
+    config: VaultConfig,
+    external_mint: bool,
+)]
 /// This InitializeVault context is used to initialize the bank vault which holds a reserve of some SPL token.
 /// 
 /// Requirements
 /// ----------------------
-/// 1) It should hold up to 10,000,000 of a custom SPL token.
-/// 2) Only some vault_admin should have the authority to mint + deposit this SPL token.
+/// 1) It should hold up to max_tokens of a custom SPL token.
+/// 2) The mint is created beforehand by the vault_admin, who hands its mint authority to the vault_info PDA.
+///    This is also how the mint of a vault created before vaults were keyed by mint is migrated: its admin
+///    still holds the mint authority, and initializing a vault over that mint moves it to the new vault_info.
+/// 3) With `external_mint`, any existing mint can be used (e.g. USDC) and its authority is left untouched.
 pub struct InitializeVault<'info> {
 
     /// This account is a PDA that holds the metadata for the vault
+    /// (sized explicitly, since its default serialization has no room for a pending admin)
     #[account(
         init,
         payer = vault_admin,
-        seeds = [VAULT_INFO_SEED.as_bytes()],
+        space = 8 + std::mem::size_of::<VaultInfo>(),
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
         bump,
     )]
     pub vault_info: Account<'info, VaultInfo>,
 
-    /// This mint account holds the mint info of the SPL token
+    /// This account is a PDA that holds the roles of the vault
     #[account(
         init,
         payer = vault_admin,
-        mint::decimals = 0,
-        mint::authority = vault_admin,
-        mint::freeze_authority = vault_admin
+        seeds = [
+            VAULT_ROLES_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub vault_roles: Box<Account<'info, VaultRoles>>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(
+        mut,
+        constraint = external_mint || token_mint.mint_authority == COption::Some(vault_admin.key())
+            @ InitializeError::AdminNotMintAuthority,
     )]
     pub token_mint: Account<'info, Mint>,
 
@@ -59,7 +165,10 @@ pub struct InitializeVault<'info> {
     #[account(
         init,
         payer = vault_admin,
-        seeds = [TOKEN_VAULT_SEED.as_bytes()],
+        seeds = [
+            TOKEN_VAULT_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
         bump,
         token::mint = token_mint,
         token::authority = vault_info,
@@ -82,24 +191,423 @@ pub struct InitializeVault<'info> {
 
 
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+/// The risk parameters of a vault, as given to `initialize` and `update_vault_config`
+pub struct VaultConfig {
+
+    /// Maximum number of tokens in vault (in whole tokens)
+    pub max_tokens: u64,
+
+    /// Annual interest rate (in bps)
+    pub annual_rate_bps: u64,
+
+    /// Whether `annual_rate_bps` is quoted as an APR or an APY
+    pub rate_basis: RateBasis,
+
+    /// Maximum number of tokens a user can lock in one deposit box (in whole tokens)
+    pub max_user_deposit: u64,
+
+    /// Share of principal forfeited to the reserve when withdrawing before maturity (in bps)
+    pub early_withdrawal_penalty_bps: u64,
+
+    /// Tokens kept in the reserve on top of promised interest, which cannot be swept (in whole tokens)
+    pub reserve_buffer: u64,
+
+    /// Annual rate matured boxes keep earning until withdrawn, quoted like `annual_rate_bps` (in bps)
+    pub post_maturity_rate_bps: u64,
+
+    /// How long after maturity boxes keep earning (in seconds, 0 turns post-maturity accrual off)
+    pub max_post_maturity_seconds: u64,
+
+    /// Whether deposits must go through a product (see `deposit_product`)
+    pub products_only: bool,
+}
+
+
+
 #[account]
 #[derive(Default)]
 /// This struct holds all of the metadata for the vault
 pub struct VaultInfo {
 
-    /// Maximum number of tokens in vault
+    /// Maximum number of tokens in vault (in base units)
     pub max_tokens: u64,
 
-    /// Interest rate (in tenths of bps)
-    pub interest_rate: u64,
+    /// Annual interest rate, as configured (in bps)
+    pub annual_rate_bps: u64,
+
+    /// Whether `annual_rate_bps` is an APR or an APY
+    pub rate_basis: RateBasis,
+
+    /// Per-second rate new deposits compound with, derived from the annual rate (in `RAY`)
+    pub rate_per_second: u128,
+
+    /// Annual rate matured boxes keep earning, as configured (in bps)
+    pub post_maturity_rate_bps: u64,
+
+    /// Per-second rate matured boxes keep earning, derived from the post-maturity rate (in `RAY`)
+    pub post_maturity_rate_per_second: u128,
+
+    /// How long after maturity new boxes keep earning (in seconds)
+    pub max_post_maturity_seconds: u64,
+
+    /// Maximum number of tokens a user can lock in one deposit box (in base units)
+    pub max_user_deposit: u64,
+
+    /// Decimals of the token mint
+    pub decimals: u8,
+
+    /// Compounding convention applied to new deposits
+    pub compounding: Compounding,
+
+    /// Share of principal forfeited to the reserve when withdrawing before maturity (in bps)
+    pub early_withdrawal_penalty_bps: u64,
+
+    /// Total principal locked in open deposit boxes
+    pub total_principal: u64,
 
-    /// The vault admin with mint+deposit authority
+    /// Total interest promised to open deposit boxes (owed from the reserve)
+    pub total_promised_interest: u64,
+
+    /// Interest owed to withdrawn boxes which the reserve could not pay yet (see `QueuedClaim`)
+    pub total_queued_interest: u64,
+
+    /// Sequence number of the oldest unpaid `QueuedClaim`
+    pub queue_head: u64,
+
+    /// Sequence number the next `QueuedClaim` is created with
+    pub queue_tail: u64,
+
+    /// Total tokens transferred into the reserve through `fund_reserve`
+    pub total_funded: u64,
+
+    /// Total tokens moved out of the reserve through `sweep_reserve` (never more than `total_funded`)
+    pub total_swept: u64,
+
+    /// Tokens kept in the reserve on top of promised interest, which cannot be swept (in base units)
+    pub reserve_buffer: u64,
+
+    /// Whether free-form deposits are rejected, leaving only product deposits open
+    pub products_only: bool,
+
+    /// The vault admin (mint authority belongs to this account's PDA)
     pub vault_admin: Pubkey,
 
+    /// The admin proposed to take over the vault, until they accept
+    pub pending_admin: Option<Pubkey>,
+
     /// The mint of the SPL token stored in the vault
     pub token_mint: Pubkey,
 
     /// The address of the vault holding the reserve
     pub token_vault: Pubkey,
 
-}
\ No newline at end of file
+    /// Whether the mint is controlled by someone else (the reserve is then funded by transfers)
+    pub external_mint: bool,
+
+    /// Whether new deposits and top-ups are paused
+    pub deposits_paused: bool,
+
+    /// Whether reserve refreshes are paused
+    pub refreshes_paused: bool,
+
+    /// Whether withdrawals are paused (emergency withdrawals stay open)
+    pub withdrawals_paused: bool,
+
+    /// Whether interest payouts are frozen, leaving users only the principal back through `emergency_withdraw`
+    pub emergency_mode: bool,
+
+}
+
+
+impl VaultInfo {
+
+    /// Sets the reserve maximum, deposit limit and reserve buffer, given in whole tokens, in base units of the mint
+    pub fn set_caps(&mut self, max_tokens: u64, max_user_deposit: u64, reserve_buffer: u64) -> Result<()> {
+        let max_tokens = to_base_units(max_tokens, self.decimals);
+        let max_user_deposit = to_base_units(max_user_deposit, self.decimals);
+        let reserve_buffer = to_base_units(reserve_buffer, self.decimals);
+        require!(
+            max_tokens.is_some() && max_user_deposit.is_some() && reserve_buffer.is_some(),
+            ConfigError::CapOverflow
+        );
+        self.max_tokens = max_tokens.unwrap();
+        self.max_user_deposit = max_user_deposit.unwrap();
+        self.reserve_buffer = reserve_buffer.unwrap();
+        Ok(())
+    }
+
+    /// Sets every risk parameter of the vault from `config` and checks them
+    pub fn apply_config(&mut self, config: &VaultConfig) -> Result<()> {
+        self.set_caps(config.max_tokens, config.max_user_deposit, config.reserve_buffer)?;
+        self.early_withdrawal_penalty_bps = config.early_withdrawal_penalty_bps;
+        self.max_post_maturity_seconds = config.max_post_maturity_seconds;
+        self.products_only = config.products_only;
+        self.set_rates(config.annual_rate_bps, config.post_maturity_rate_bps, config.rate_basis)?;
+        self.validate_config()
+    }
+
+    /// Sets the annual rates and derives their per-second rates under the vault's compounding convention
+    pub fn set_rates(&mut self, annual_rate_bps: u64, post_maturity_rate_bps: u64, rate_basis: RateBasis) -> Result<()> {
+        let rate_per_second = annual_bps_to_per_second_ray(annual_rate_bps, rate_basis, self.compounding);
+        let post_maturity_rate_per_second = annual_bps_to_per_second_ray(post_maturity_rate_bps, rate_basis, self.compounding);
+        require!(
+            rate_per_second.is_some() && post_maturity_rate_per_second.is_some(),
+            ConfigError::InterestRateOutOfBounds
+        );
+        self.annual_rate_bps = annual_rate_bps;
+        self.post_maturity_rate_bps = post_maturity_rate_bps;
+        self.rate_basis = rate_basis;
+        self.rate_per_second = rate_per_second.unwrap();
+        self.post_maturity_rate_per_second = post_maturity_rate_per_second.unwrap();
+        Ok(())
+    }
+
+    /// Per-second post-maturity rate for a box compounding under `compounding`. The stored rate was
+    /// derived under the vault's convention, boxes opened through a product may use another one.
+    pub fn post_maturity_rate_for(&self, compounding: Compounding) -> Option<u128> {
+        if compounding == self.compounding {
+            return Some(self.post_maturity_rate_per_second);
+        }
+        annual_bps_to_per_second_ray(self.post_maturity_rate_bps, self.rate_basis, compounding)
+    }
+
+    /// Checks the vault parameters are within bounds
+    pub fn validate_config(&self) -> Result<()> {
+        require!(self.max_tokens > 0, ConfigError::ZeroMaxTokens);
+        require!(
+            self.annual_rate_bps > 0 && self.annual_rate_bps <= MAX_ANNUAL_RATE_BPS,
+            ConfigError::InterestRateOutOfBounds
+        );
+        require!(
+            self.post_maturity_rate_bps <= MAX_ANNUAL_RATE_BPS,
+            ConfigError::InterestRateOutOfBounds
+        );
+        require!(
+            self.max_user_deposit > 0 && self.max_user_deposit <= self.max_tokens,
+            ConfigError::InvalidMaxUserDeposit
+        );
+        require!(
+            self.early_withdrawal_penalty_bps <= MAX_EARLY_WITHDRAWAL_PENALTY_BPS,
+            ConfigError::PenaltyOutOfBounds
+        );
+        Ok(())
+    }
+
+    /// Records a deposit box and the interest it was promised in the vault's liabilities
+    pub fn record_deposit(&mut self, principal: u64, promised_interest: u64) -> Option<()> {
+        self.total_principal = self.total_principal.checked_add(principal)?;
+        self.total_promised_interest = self.total_promised_interest.checked_add(promised_interest)?;
+        Some(())
+    }
+
+    /// Releases a deposit box from the vault's liabilities
+    pub fn release_deposit(&mut self, principal: u64, promised_interest: u64) -> Option<()> {
+        self.total_principal = self.total_principal.checked_sub(principal)?;
+        self.total_promised_interest = self.total_promised_interest.checked_sub(promised_interest)?;
+        Some(())
+    }
+
+    /// Records interest owed to a withdrawn box which the reserve could not pay, and returns the
+    /// sequence number of the `QueuedClaim` holding it, behind all others
+    pub fn queue_interest(&mut self, amount: u64) -> Option<u64> {
+        self.total_queued_interest = self.total_queued_interest.checked_add(amount)?;
+        let sequence = self.queue_tail;
+        self.queue_tail = sequence.checked_add(1)?;
+        Some(sequence)
+    }
+
+    /// Releases queued interest once it has been paid out of the reserve towards the oldest claim,
+    /// which leaves the queue once `paid_off`
+    pub fn settle_queued_interest(&mut self, amount: u64, paid_off: bool) -> Option<()> {
+        self.total_queued_interest = self.total_queued_interest.checked_sub(amount)?;
+        if paid_off {
+            self.queue_head = self.queue_head.checked_add(1).filter(|head| *head <= self.queue_tail)?;
+        }
+        Some(())
+    }
+
+    /// Interest owed to depositors: promised to open deposits plus queued for withdrawn ones
+    pub fn liabilities(&self) -> Option<u64> {
+        self.total_promised_interest.checked_add(self.total_queued_interest)
+    }
+
+    /// Whether a reserve holding `reserve_amount` covers all promised and queued interest
+    pub fn is_solvent(&self, reserve_amount: u64) -> bool {
+        self.liabilities().map_or(false, |liabilities| reserve_amount >= liabilities)
+    }
+
+    /// Tokens of a reserve holding `reserve_amount` that are neither owed to depositors nor part of the buffer
+    pub fn excess_reserve(&self, reserve_amount: u64) -> u64 {
+        reserve_amount
+            .saturating_sub(self.liabilities().unwrap_or(u64::MAX))
+            .saturating_sub(self.reserve_buffer)
+    }
+
+    /// Tokens the admin can sweep out of a reserve holding `reserve_amount`: excess reserve, up to what
+    /// was funded and not swept yet. Minted reserve can never leave the vault other than as interest.
+    pub fn sweepable_reserve(&self, reserve_amount: u64) -> u64 {
+        self.excess_reserve(reserve_amount)
+            .min(self.total_funded.saturating_sub(self.total_swept))
+    }
+}
+
+
+#[error_code]
+pub enum InitializeError {
+    #[msg("The vault admin must be the mint authority of the token mint")]
+    AdminNotMintAuthority,
+}
+
+#[test]
+fn test_validate_config(){
+    let vault_info = VaultInfo {
+        max_tokens: 10_000_000,
+        annual_rate_bps: 500,
+        max_user_deposit: 100_000,
+        ..Default::default()
+    };
+    assert!(vault_info.validate_config().is_ok());
+
+    assert!(VaultInfo { max_tokens: 0, max_user_deposit: 0, ..vault_info }.validate_config().is_err());
+    assert!(VaultInfo { annual_rate_bps: 0, ..vault_info }.validate_config().is_err());
+    assert!(VaultInfo { annual_rate_bps: MAX_ANNUAL_RATE_BPS + 1, ..vault_info }.validate_config().is_err());
+    assert!(VaultInfo { post_maturity_rate_bps: MAX_ANNUAL_RATE_BPS + 1, ..vault_info }.validate_config().is_err());
+    assert!(VaultInfo { max_user_deposit: 0, ..vault_info }.validate_config().is_err());
+    assert!(VaultInfo { max_user_deposit: 10_000_001, ..vault_info }.validate_config().is_err());
+    assert!(VaultInfo { early_withdrawal_penalty_bps: MAX_EARLY_WITHDRAWAL_PENALTY_BPS, ..vault_info }.validate_config().is_ok());
+    assert!(VaultInfo { early_withdrawal_penalty_bps: MAX_EARLY_WITHDRAWAL_PENALTY_BPS + 1, ..vault_info }.validate_config().is_err());
+}
+
+#[test]
+fn test_liabilities_tracking(){
+    let mut vault_info = VaultInfo::default();
+
+    vault_info.record_deposit(100_000, 500).unwrap();
+    vault_info.record_deposit(50_000, 300).unwrap();
+    assert_eq!(vault_info.total_principal, 150_000);
+    assert_eq!(vault_info.total_promised_interest, 800);
+
+    // The reserve must cover all promised interest
+    assert!(vault_info.is_solvent(800));
+    assert!(!vault_info.is_solvent(799));
+
+    vault_info.release_deposit(100_000, 500).unwrap();
+    assert_eq!(vault_info.total_principal, 50_000);
+    assert_eq!(vault_info.total_promised_interest, 300);
+
+    // Releasing more than was recorded is an accounting error
+    assert!(vault_info.release_deposit(50_001, 0).is_none());
+
+    // Queued interest counts towards the liabilities as well
+    vault_info.queue_interest(200).unwrap();
+    assert_eq!(vault_info.liabilities(), Some(500));
+    assert!(!vault_info.is_solvent(499));
+    vault_info.queue_interest(u64::MAX - 499).unwrap();
+    assert_eq!(vault_info.liabilities(), None);
+    assert!(!vault_info.is_solvent(u64::MAX));
+}
+
+#[test]
+fn test_caps_are_given_in_whole_tokens(){
+    let mut vault_info = VaultInfo { decimals: 6, ..Default::default() };
+    vault_info.set_caps(10_000_000, 100_000, 1_000).unwrap();
+    assert_eq!(vault_info.max_tokens, 10_000_000_000_000);
+    assert_eq!(vault_info.max_user_deposit, 100_000_000_000);
+    assert_eq!(vault_info.reserve_buffer, 1_000_000_000);
+
+    // Caps which don't fit in base units are rejected
+    let mut vault_info = VaultInfo { decimals: 9, ..Default::default() };
+    assert!(vault_info.set_caps(u64::MAX / 1_000_000_000 + 1, 1, 0).is_err());
+}
+
+#[test]
+fn test_excess_reserve_keeps_promises_and_buffer(){
+    let mut vault_info = VaultInfo { reserve_buffer: 1_000, ..Default::default() };
+    vault_info.record_deposit(100_000, 5_000).unwrap();
+
+    assert_eq!(vault_info.excess_reserve(10_000), 4_000);
+    assert_eq!(vault_info.excess_reserve(6_000), 0);
+
+    // A reserve that is already short has nothing to sweep
+    assert_eq!(vault_info.excess_reserve(3_000), 0);
+
+    // Interest queued for withdrawn boxes is still owed
+    assert_eq!(vault_info.queue_interest(1_500), Some(0));
+    assert_eq!(vault_info.queue_interest(500), Some(1));
+    assert_eq!(vault_info.excess_reserve(10_000), 2_000);
+    assert!(!vault_info.is_solvent(6_000));
+
+    // Claims are settled from the head of the queue, which moves on once a claim is paid off
+    vault_info.settle_queued_interest(1_000, false).unwrap();
+    assert_eq!((vault_info.queue_head, vault_info.queue_tail), (0, 2));
+    vault_info.settle_queued_interest(500, true).unwrap();
+    vault_info.settle_queued_interest(500, true).unwrap();
+    assert_eq!((vault_info.queue_head, vault_info.queue_tail), (2, 2));
+    assert!(vault_info.settle_queued_interest(1, false).is_none());
+    assert!(vault_info.settle_queued_interest(0, true).is_none());
+}
+
+#[cfg(test)]
+use crate::math::SECONDS_PER_YEAR;
+
+#[test]
+fn test_config_derives_per_second_rate(){
+    let config = VaultConfig {
+        max_tokens: 10_000_000,
+        annual_rate_bps: 500,
+        rate_basis: RateBasis::Apy,
+        max_user_deposit: 100_000,
+        ..Default::default()
+    };
+    let mut vault_info = VaultInfo::default();
+    vault_info.apply_config(&config).unwrap();
+    assert_eq!(vault_info.annual_rate_bps, 500);
+    assert_eq!(vault_info.rate_basis, RateBasis::Apy);
+    assert_eq!(
+        Some(vault_info.rate_per_second),
+        annual_bps_to_per_second_ray(500, RateBasis::Apy, Compounding::PerSecond)
+    );
+
+    // The same APY needs a higher per-second rate when interest does not compound
+    let per_second = vault_info.rate_per_second;
+    let mut vault_info = VaultInfo { compounding: Compounding::Simple, ..Default::default() };
+    vault_info.apply_config(&config).unwrap();
+    assert!(vault_info.rate_per_second > per_second);
+
+    assert_eq!(vault_info.post_maturity_rate_per_second, 0);
+
+    // Matured boxes can keep earning, here at a lower rate for up to a year
+    let mut vault_info = VaultInfo::default();
+    vault_info.apply_config(&VaultConfig { post_maturity_rate_bps: 200, max_post_maturity_seconds: SECONDS_PER_YEAR, ..config.clone() }).unwrap();
+    assert_eq!(
+        Some(vault_info.post_maturity_rate_per_second),
+        annual_bps_to_per_second_ray(200, RateBasis::Apy, Compounding::PerSecond)
+    );
+    assert_eq!(vault_info.max_post_maturity_seconds, SECONDS_PER_YEAR);
+
+    // The catalog restriction is part of the config
+    let mut vault_info = VaultInfo::default();
+    vault_info.apply_config(&VaultConfig { products_only: true, ..config.clone() }).unwrap();
+    assert!(vault_info.products_only);
+
+    assert!(VaultInfo::default().apply_config(&VaultConfig { annual_rate_bps: 0, ..config }).is_err());
+}
+
+#[test]
+fn test_only_funded_reserve_can_be_swept(){
+    let mut vault_info = VaultInfo { reserve_buffer: 1_000, ..Default::default() };
+
+    // A reserve minted up by `refresh_reserve` has excess, but none of it was funded
+    assert_eq!(vault_info.excess_reserve(10_000), 9_000);
+    assert_eq!(vault_info.sweepable_reserve(10_000), 0);
+
+    // Funded tokens can be swept back out, once
+    vault_info.total_funded = 4_000;
+    assert_eq!(vault_info.sweepable_reserve(10_000), 4_000);
+    vault_info.total_swept = 3_000;
+    assert_eq!(vault_info.sweepable_reserve(10_000), 1_000);
+
+    // ...and never from what is owed or buffered
+    assert_eq!(vault_info.sweepable_reserve(1_500), 500);
+}
@@ -1,4 +1,20 @@
 pub mod initialize;
 pub mod deposit;
 pub mod withdraw;
-pub mod refresh_reserve;
\ No newline at end of file
+pub mod refresh_reserve;
+pub mod top_up;
+pub mod update_vault_config;
+pub mod withdraw_early;
+pub mod fund_reserve;
+pub mod sweep_reserve;
+pub mod propose_admin;
+pub mod accept_admin;
+pub mod cancel_admin_transfer;
+pub mod set_role;
+pub mod set_pause;
+pub mod emergency_withdraw;
+pub mod set_rate_tiers;
+pub mod set_product;
+pub mod deposit_product;
+pub mod set_compounding;
+pub mod withdraw_legacy;
\ No newline at end of file
@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::{Mint, Token};
+use spl_token::{self, instruction::AuthorityType};
+use solana_program;
+
+use crate::instructions::initialize::VaultInfo;
+use crate::events::AdminProposed;
+use crate::constants::*;
+
+
+pub fn handler(
+    ctx: Context<ProposeAdmin>,
+    new_admin: Pubkey,
+) -> Result<()> {
+
+    let token_mint = &ctx.accounts.token_mint;
+    let vault_admin = ctx.accounts.vault_admin.key();
+
+    // Escrow the admin's freeze authority in the vault until the transfer is accepted or cancelled,
+    // unless the mint is external and its authorities are not the vault's to move
+    if !ctx.accounts.vault_info.external_mint && token_mint.freeze_authority == COption::Some(vault_admin) {
+
+        // Construct instruction using spl_token library
+        let ix = spl_token::instruction::set_authority(
+
+            // token_program_id: &Pubkey,
+            // owned_pubkey: &Pubkey,
+            // new_authority_pubkey: Option<&Pubkey>,
+            // authority_type: AuthorityType,
+            // owner_pubkey: &Pubkey,
+            // signer_pubkeys: &[&Pubkey]
+
+            &ctx.accounts.token_program.key(),
+            &token_mint.key(),
+            Some(&ctx.accounts.vault_info.key()),
+            AuthorityType::FreezeAccount,
+            &vault_admin,
+            &[&vault_admin],
+        )?;
+
+        // Invoke using solana_program library
+        solana_program::program::invoke(
+            &ix,
+            &[
+                token_mint.to_account_info(),
+                ctx.accounts.vault_admin.to_account_info(),
+            ],
+        )?;
+    }
+
+    // A new proposal replaces any pending one
+    let vault_info = &mut ctx.accounts.vault_info;
+    vault_info.pending_admin = Some(new_admin);
+
+    emit!(AdminProposed {
+        vault_info: vault_info.key(),
+        vault_admin,
+        pending_admin: new_admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Moves the freeze authority of `token_mint` from the vault_info PDA to `new_authority`,
+/// if the vault is holding it in escrow. External mints are never escrowed.
+pub fn release_freeze_authority<'info>(
+    token_program: &Program<'info, Token>,
+    token_mint: &Account<'info, Mint>,
+    vault_info: &Account<'info, VaultInfo>,
+    info_bump: u8,
+    new_authority: &Pubkey,
+) -> Result<()> {
+
+    if vault_info.external_mint || token_mint.freeze_authority != COption::Some(vault_info.key()) {
+        return Ok(());
+    }
+
+    // Construct instruction using spl_token library
+    let ix = spl_token::instruction::set_authority(
+        &token_program.key(),
+        &token_mint.key(),
+        Some(new_authority),
+        AuthorityType::FreezeAccount,
+        &vault_info.key(),
+        &[&vault_info.key()],
+    )?;
+
+    // Invoke using solana_program library, signing as the vault_info PDA
+    let mint_key = token_mint.key();
+    solana_program::program::invoke_signed(
+        &ix,
+        &[
+            token_mint.to_account_info(),
+            vault_info.to_account_info(),
+        ],
+        &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[info_bump]]],
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(
+    info_bump: u8,
+)]
+/// This ProposeAdmin context is used by the vault admin to start handing the vault over.
+///
+/// Requirements
+/// ----------------------
+/// 1) Only the vault admin can propose, and the proposed admin must accept (see `AcceptAdmin`).
+/// 2) The admin's freeze authority is escrowed in the vault_info PDA until then, unless the mint is external.
+pub struct ProposeAdmin<'info> {
+
+    /// This account holds the metadata for the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = info_bump,
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(mut, address=vault_info.token_mint)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// This is the vault admin
+    #[account(address=vault_info.vault_admin)]
+    pub vault_admin: Signer<'info>,
+
+    /// Token Program
+    pub token_program: Program<'info, Token>,
+}
+
+
+#[error_code]
+pub enum AdminTransferError {
+    #[msg("There is no pending admin transfer")]
+    NoPendingAdmin,
+    #[msg("Signer is not the pending admin")]
+    NotPendingAdmin,
+}
@@ -1,24 +1,28 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
 use anchor_spl::token::{TokenAccount, Mint, Token};
 use spl_token;
 use solana_program;
 
 use crate::instructions::initialize::VaultInfo;
+use crate::instructions::set_role::{VaultRoles, RoleError};
+use crate::instructions::withdraw::settle_queued_claims;
+use crate::events::ReserveRefreshed;
 use crate::constants::*;
 
 
-pub fn handler(
-    ctx: Context<RefreshReserve>,
-    reserve_bump: u8,
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, RefreshReserve<'info>>,
+    info_bump: u8,
     ) -> Result<()> {
 
     // Compute refresh amount
-    let refresh_amount = MAX_RESERVE_TOKEN_AMOUNT.checked_sub(ctx.accounts.token_vault.amount);
+    let refresh_amount = ctx.accounts.vault_info.max_tokens.checked_sub(ctx.accounts.token_vault.amount);
     require!(refresh_amount.is_some(), RefreshError::MaxReserveLimit);
+    let refresh_amount = refresh_amount.unwrap();
 
     require!(*ctx.program_id == ctx.accounts.program.key(), RefreshError::InvalidProgramId);
 
-    msg!("Creating mint ix");
     // Construct instruction using spl_token library
     let ix = spl_token::instruction::mint_to(
 
@@ -28,48 +32,49 @@ pub fn handler(
         // owner_pubkey: &Pubkey, 
         // signer_pubkeys: &[&Pubkey], 
         // amount: u64
-        // decimals: u8
 
         &ctx.accounts.token_program.key(),
         &ctx.accounts.token_mint.key(),
         &ctx.accounts.token_vault.key(),
-        &ctx.accounts.vault_admin.key(),//&ctx.program_id,
-        &[],//&ctx.accounts.vault_admin.key()],
-        refresh_amount.unwrap(),
-        //ctx.accounts.token_mint.decimals,
+        &ctx.accounts.vault_info.key(),
+        &[&ctx.accounts.vault_info.key()],
+        refresh_amount,
     )?;
 
-    msg!("mint ix invoke");
-    msg!("token program: {}", ctx.accounts.token_program.key());
-    msg!("mint key: {}", ctx.accounts.token_mint.key());
-    msg!("program id: {}", ctx.accounts.program.key());
-    msg!("vault info: {}", ctx.accounts.vault_info.key());
-    msg!("vault key: {}", ctx.accounts.token_vault.key());
-    msg!("admin key: {}", ctx.accounts.vault_admin.key());
-    // Invoke using solana_program library
-    let seeds: &[&[u8]] = &[TOKEN_VAULT_SEED.as_bytes()];
+    // Invoke using solana_program library, signing as the vault_info PDA (the mint authority)
+    let mint_key = ctx.accounts.token_mint.key();
     solana_program::program::invoke_signed(
         &ix,
         &[
-            //ctx.accounts.token_program.to_account_info(),
             ctx.accounts.token_mint.to_account_info(),
-            //ctx.accounts.program.to_account_info(),
             ctx.accounts.token_vault.to_account_info(),
-            ctx.accounts.vault_admin.to_account_info(),
+            ctx.accounts.vault_info.to_account_info(),
         ],
-        //&[&[TOKEN_VAULT_SEED.as_bytes()]],
-        &[&[seeds, &[&[reserve_bump]]].concat()],
+        &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[info_bump]]],
     ).expect("failed to refresh reserve");
 
-    Ok(())
+    // Pay queued interest out of the refreshed reserve (see `settle_queued_claims`)
+    ctx.accounts.token_vault.reload()?;
+    let settled_interest = settle_queued_claims(
+        ctx.program_id,
+        ctx.remaining_accounts,
+        &mut ctx.accounts.vault_info,
+        &mut ctx.accounts.token_vault,
+        &ctx.accounts.token_mint,
+        &ctx.accounts.token_program,
+        info_bump,
+    )?;
 
-    // mint_to(
-    //     &ctx.accounts.token_vault.to_account_info(), 
-    //     &ctx.accounts.token_mint.to_account_info(),
-    //     &ctx.accounts.vault_admin.to_account_info(),
-    //     &ctx.accounts.program.key(),//base_address: &Pubkey, 
-    //     &[TOKEN_VAULT_SEED.as_bytes()], refresh_amount.unwrap() //seeds: &[&[u8]], amount: u64)
-    // )
+    emit!(ReserveRefreshed {
+        vault_info: ctx.accounts.vault_info.key(),
+        token_vault: ctx.accounts.token_vault.key(),
+        amount: refresh_amount,
+        settled_interest,
+        reserve_balance: ctx.accounts.token_vault.amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
 }
 
 #[derive(Accounts)]
@@ -81,33 +86,57 @@ pub fn handler(
 /// 
 /// Requirements
 /// ----------------------
-/// 1) It should hold up to 10,000,000 of a custom SPL token.
-/// 2) Only some admin should have the authority to mint + deposit this SPL token.
+/// 1) It should hold up to vault_info.max_tokens of a custom SPL token.
+/// 2) Only the operator can trigger a refresh, but the mint authority is the vault_info PDA.
+/// 3) Queued interest is settled when the oldest `QueuedClaim`s, their token accounts and claimants are passed as remaining accounts.
 pub struct RefreshReserve<'info> {
 
     /// This account holds the metadata for the vault
     #[account(
-        seeds = [VAULT_INFO_SEED.as_bytes()],
+        mut,
+        constraint = !vault_info.external_mint @ RefreshError::ExternalMint,
+        constraint = !vault_info.refreshes_paused @ RefreshError::RefreshesPaused,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
         bump = info_bump,
     )]
     pub vault_info: Account<'info, VaultInfo>,
 
+    /// This account holds the roles of the vault
+    #[account(
+        seeds = [
+            VAULT_ROLES_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub vault_roles: Account<'info, VaultRoles>,
+
     /// This token account serves as the reserve for the SPL token
     #[account(
         mut,
-        seeds = [TOKEN_VAULT_SEED.as_bytes()],
+        seeds = [
+            TOKEN_VAULT_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
         bump = reserve_bump,
     )]
     pub token_vault: Account<'info, TokenAccount>,
 
     /// This mint account holds the mint info of the SPL token
-    #[account(mut, address=vault_info.token_mint)]
+    #[account(
+        mut,
+        address=vault_info.token_mint,
+        constraint = token_mint.mint_authority == COption::Some(vault_info.key())
+            @ RefreshError::VaultNotMintAuthority,
+    )]
     pub token_mint: Account<'info, Mint>,
 
-    /// This is the vault admin
-    /// CHECK: This is fine because we are ensuring address=vault_info.admin
-    #[account(address=vault_info.vault_admin)]
-    pub vault_admin: Signer<'info>,
+    /// This is the operator
+    #[account(address=vault_roles.operator @ RoleError::MissingRole)]
+    pub operator: Signer<'info>,
 
     /// System Program
     pub system_program: Program<'info, System>,
@@ -149,4 +178,10 @@ pub enum RefreshError {
     MaxReserveLimit,
     #[msg("Passed in wrong program_id")]
     InvalidProgramId,
+    #[msg("The vault is not the mint authority of its token")]
+    VaultNotMintAuthority,
+    #[msg("The reserve of a vault over an external mint is funded by transfers")]
+    ExternalMint,
+    #[msg("Reserve refreshes are paused")]
+    RefreshesPaused,
 }
\ No newline at end of file
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::instructions::initialize::VaultInfo;
+use crate::instructions::set_role::{VaultRoles, RoleError};
+use crate::instructions::set_rate_tiers::RateTiers;
+use crate::events::CompoundingUpdated;
+use crate::constants::*;
+use crate::math::Compounding;
+
+
+pub fn handler(
+    ctx: Context<SetCompounding>,
+    compounding: Compounding,
+) -> Result<()> {
+
+    // New deposits compound with the new convention, at the per-second rates that yield
+    // the same annual rates under it. Open deposits keep the convention and rates they were
+    // opened with, since they are stored in their DepositInfo.
+    let vault_info = &mut ctx.accounts.vault_info;
+    vault_info.compounding = compounding;
+    let (annual_rate_bps, post_maturity_rate_bps, rate_basis) =
+        (vault_info.annual_rate_bps, vault_info.post_maturity_rate_bps, vault_info.rate_basis);
+    vault_info.set_rates(annual_rate_bps, post_maturity_rate_bps, rate_basis)?;
+
+    // Published brackets are re-derived the same way
+    let rate_tiers = &ctx.accounts.rate_tiers;
+    if rate_tiers.owner == ctx.program_id && !rate_tiers.data_is_empty() {
+        let mut rate_tiers = Account::<RateTiers>::try_from(rate_tiers)?;
+        rate_tiers.set_rates(compounding)?;
+        rate_tiers.exit(ctx.program_id)?;
+    }
+
+    emit!(CompoundingUpdated {
+        vault_info: vault_info.key(),
+        risk_manager: ctx.accounts.risk_manager.key(),
+        compounding,
+        rate_per_second: vault_info.rate_per_second,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(
+    info_bump: u8,
+)]
+/// This SetCompounding context is used to choose how the vault's interest compounds.
+///
+/// Requirements
+/// ----------------------
+/// 1) Only the risk manager can change the convention.
+/// 2) The per-second rates of the vault and of its rate brackets are re-derived under the new convention.
+/// 3) Products carry their own convention (see `ProductTerms`).
+pub struct SetCompounding<'info> {
+
+    /// This account holds the metadata for the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = info_bump,
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    /// This account holds the roles of the vault
+    #[account(
+        seeds = [
+            VAULT_ROLES_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub vault_roles: Account<'info, VaultRoles>,
+
+    /// This account holds the rate table of the vault, if the risk manager published one
+    /// CHECK: This is fine because it is only written when it is a `RateTiers` account owned by our program
+    #[account(
+        mut,
+        seeds = [
+            RATE_TIERS_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub rate_tiers: UncheckedAccount<'info>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(address=vault_info.token_mint)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// This is the risk manager
+    #[account(address=vault_roles.risk_manager @ RoleError::MissingRole)]
+    pub risk_manager: Signer<'info>,
+}
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::instructions::initialize::VaultInfo;
+use crate::instructions::set_role::{VaultRoles, RoleError};
+use crate::events::PauseUpdated;
+use crate::constants::*;
+
+
+pub fn handler(
+    ctx: Context<SetPause>,
+    deposits_paused: bool,
+    refreshes_paused: bool,
+    withdrawals_paused: bool,
+    emergency_mode: bool,
+) -> Result<()> {
+
+    // Update pause flags
+    let vault_info = &mut ctx.accounts.vault_info;
+    vault_info.deposits_paused = deposits_paused;
+    vault_info.refreshes_paused = refreshes_paused;
+    vault_info.withdrawals_paused = withdrawals_paused;
+    vault_info.emergency_mode = emergency_mode;
+
+    emit!(PauseUpdated {
+        vault_info: vault_info.key(),
+        pauser: ctx.accounts.pauser.key(),
+        deposits_paused,
+        refreshes_paused,
+        withdrawals_paused,
+        emergency_mode,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(
+    info_bump: u8,
+)]
+/// This SetPause context is used to stop (or resume) parts of the vault.
+///
+/// Requirements
+/// ----------------------
+/// 1) Only the pauser can change the flags.
+/// 2) Deposits, refreshes and withdrawals are paused independently. Emergency mode freezes
+///    interest payouts, and users can always recover their principal with `emergency_withdraw`.
+pub struct SetPause<'info> {
+
+    /// This account holds the metadata for the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = info_bump,
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    /// This account holds the roles of the vault
+    #[account(
+        seeds = [
+            VAULT_ROLES_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub vault_roles: Account<'info, VaultRoles>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(address=vault_info.token_mint)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// This is the pauser
+    #[account(address=vault_roles.pauser @ RoleError::MissingRole)]
+    pub pauser: Signer<'info>,
+}
@@ -0,0 +1,306 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::instructions::initialize::VaultInfo;
+use crate::instructions::set_role::{VaultRoles, RoleError};
+use crate::instructions::update_vault_config::ConfigError;
+use crate::events::ProductUpdated;
+use crate::constants::*;
+use crate::math::{annual_bps_to_per_second_ray, to_base_units, Compounding, RateBasis};
+
+
+pub fn handler(
+    ctx: Context<SetProduct>,
+    product_id: u64,
+    terms: ProductTerms,
+) -> Result<()> {
+
+    // Publish (or change) the product. Open boxes keep the term and rate they were opened with.
+    let vault_info = &ctx.accounts.vault_info;
+    let product = &mut ctx.accounts.product;
+    product.vault_info = vault_info.key();
+    product.product_id = product_id;
+    product.set_terms(terms, vault_info.decimals)?;
+
+    emit!(ProductUpdated {
+        vault_info: product.vault_info,
+        product: product.key(),
+        product_id,
+        name: product.name.clone(),
+        seconds_locked: product.seconds_locked,
+        annual_rate_bps: product.annual_rate_bps,
+        rate_basis: product.rate_basis,
+        rate_per_second: product.rate_per_second,
+        compounding: product.compounding,
+        min_deposit: product.min_deposit,
+        max_deposit: product.max_deposit,
+        capacity: product.capacity,
+        open: product.open,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(
+    info_bump: u8,
+    product_id: u64,
+)]
+/// This SetProduct context is used to publish a deposit product (a fixed term certificate) or change its terms.
+///
+/// Requirements
+/// ----------------------
+/// 1) Only the risk manager can manage products.
+/// 2) Amounts are given in whole tokens, and the capacity cannot drop below the product's open principal.
+/// 3) Closing a product stops new deposits, open boxes are unaffected.
+pub struct SetProduct<'info> {
+
+    /// This account holds the metadata for the vault
+    #[account(
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = info_bump,
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    /// This account holds the roles of the vault
+    #[account(
+        seeds = [
+            VAULT_ROLES_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub vault_roles: Account<'info, VaultRoles>,
+
+    /// This account is a PDA that holds the product (created on first use)
+    #[account(
+        init_if_needed,
+        payer = risk_manager,
+        space = Product::SPACE,
+        seeds = [
+            PRODUCT_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+            &product_id.to_le_bytes(),
+        ],
+        bump,
+    )]
+    pub product: Account<'info, Product>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(address=vault_info.token_mint)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// This is the risk manager
+    #[account(mut, address=vault_roles.risk_manager @ RoleError::MissingRole)]
+    pub risk_manager: Signer<'info>,
+
+    /// System Program
+    pub system_program: Program<'info, System>,
+}
+
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, Debug, PartialEq)]
+/// Terms of a deposit product, as published by the risk manager
+pub struct ProductTerms {
+
+    /// Name of the product (e.g. "30-day")
+    pub name: String,
+
+    /// Term of the product (in seconds)
+    pub seconds_locked: u64,
+
+    /// Annual interest rate of the product (in bps)
+    pub annual_rate_bps: u64,
+
+    /// Whether `annual_rate_bps` is an APR or an APY
+    pub rate_basis: RateBasis,
+
+    /// Compounding convention of the product
+    pub compounding: Compounding,
+
+    /// Smallest deposit (in whole tokens)
+    pub min_deposit: u64,
+
+    /// Largest deposit (in whole tokens)
+    pub max_deposit: u64,
+
+    /// Maximum open principal across the product's boxes (in whole tokens)
+    pub capacity: u64,
+
+    /// Whether the product takes new deposits
+    pub open: bool,
+}
+
+
+#[account]
+#[derive(Default)]
+/// This struct holds a deposit product of a vault
+pub struct Product {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// Id of the product within the vault
+    pub product_id: u64,
+
+    /// Name of the product
+    pub name: String,
+
+    /// Term of the product (in seconds)
+    pub seconds_locked: u64,
+
+    /// Annual interest rate of the product (in bps)
+    pub annual_rate_bps: u64,
+
+    /// Whether `annual_rate_bps` is an APR or an APY
+    pub rate_basis: RateBasis,
+
+    /// Per-second rate the product's boxes compound with, derived from the annual rate (in `RAY`)
+    pub rate_per_second: u128,
+
+    /// Compounding convention of the product
+    pub compounding: Compounding,
+
+    /// Smallest deposit (in base units)
+    pub min_deposit: u64,
+
+    /// Largest deposit (in base units)
+    pub max_deposit: u64,
+
+    /// Maximum open principal across the product's boxes (in base units)
+    pub capacity: u64,
+
+    /// Principal of the product's open boxes (in base units)
+    pub total_principal: u64,
+
+    /// Number of open boxes
+    pub open_count: u64,
+
+    /// Whether the product takes new deposits
+    pub open: bool,
+}
+
+impl Product {
+
+    /// Account size with room for a name of `MAX_PRODUCT_NAME_LEN` bytes
+    pub const SPACE: usize = 8 + 32 + 8 + 4 + MAX_PRODUCT_NAME_LEN + 8 * 7 + 1 + 16 + 1 + 1;
+
+    /// Validates and stores new terms, converting amounts from whole tokens to base units
+    /// and the annual rate to a per-second rate under the product's compounding convention
+    pub fn set_terms(&mut self, terms: ProductTerms, decimals: u8) -> Result<()> {
+        require!(terms.name.len() <= MAX_PRODUCT_NAME_LEN, ProductError::NameTooLong);
+        require!(terms.seconds_locked > 0, ProductError::ZeroTerm);
+        require!(
+            terms.annual_rate_bps > 0 && terms.annual_rate_bps <= MAX_ANNUAL_RATE_BPS,
+            ConfigError::InterestRateOutOfBounds
+        );
+        let rate_per_second = annual_bps_to_per_second_ray(terms.annual_rate_bps, terms.rate_basis, terms.compounding);
+        require!(rate_per_second.is_some(), ConfigError::InterestRateOutOfBounds);
+        require!(
+            terms.max_deposit > 0 && terms.min_deposit <= terms.max_deposit && terms.max_deposit <= terms.capacity,
+            ProductError::InvalidDepositRange
+        );
+
+        let min_deposit = to_base_units(terms.min_deposit, decimals);
+        let max_deposit = to_base_units(terms.max_deposit, decimals);
+        let capacity = to_base_units(terms.capacity, decimals);
+        require!(
+            min_deposit.is_some() && max_deposit.is_some() && capacity.is_some(),
+            ConfigError::CapOverflow
+        );
+        require!(capacity.unwrap() >= self.total_principal, ProductError::CapacityBelowOpenPrincipal);
+
+        self.name = terms.name;
+        self.seconds_locked = terms.seconds_locked;
+        self.annual_rate_bps = terms.annual_rate_bps;
+        self.rate_basis = terms.rate_basis;
+        self.rate_per_second = rate_per_second.unwrap();
+        self.compounding = terms.compounding;
+        self.min_deposit = min_deposit.unwrap();
+        self.max_deposit = max_deposit.unwrap();
+        self.capacity = capacity.unwrap();
+        self.open = terms.open;
+        Ok(())
+    }
+
+    /// Records a box opened through the product, or returns None if it would exceed the capacity
+    pub fn record_deposit(&mut self, principal: u64) -> Option<()> {
+        let total_principal = self.total_principal.checked_add(principal)?;
+        if total_principal > self.capacity {
+            return None;
+        }
+        self.total_principal = total_principal;
+        self.open_count = self.open_count.checked_add(1)?;
+        Some(())
+    }
+
+    /// Releases a closed box from the product
+    pub fn release_deposit(&mut self, principal: u64) -> Option<()> {
+        self.total_principal = self.total_principal.checked_sub(principal)?;
+        self.open_count = self.open_count.checked_sub(1)?;
+        Some(())
+    }
+}
+
+
+#[error_code]
+pub enum ProductError {
+    #[msg("Product name is too long")]
+    NameTooLong,
+    #[msg("Product term must be nonzero")]
+    ZeroTerm,
+    #[msg("Deposit range must be nonzero, ordered and within the capacity")]
+    InvalidDepositRange,
+    #[msg("Capacity cannot drop below the principal of the product's open boxes")]
+    CapacityBelowOpenPrincipal,
+    #[msg("The product does not take new deposits")]
+    ProductClosed,
+    #[msg("Deposit is outside the product's deposit range")]
+    OutsideDepositRange,
+    #[msg("The product is at capacity")]
+    CapacityReached,
+    #[msg("The product account of this deposit box is missing or wrong")]
+    WrongProduct,
+}
+
+
+#[test]
+fn test_product_terms_and_capacity(){
+    let terms = ProductTerms {
+        name: "30-day".to_string(),
+        seconds_locked: 30 * 86_400,
+        annual_rate_bps: 400,
+        rate_basis: RateBasis::Apy,
+        compounding: Compounding::Daily,
+        min_deposit: 10,
+        max_deposit: 100,
+        capacity: 150,
+        open: true,
+    };
+    let mut product = Product::default();
+    product.set_terms(terms.clone(), 6).unwrap();
+    assert_eq!(product.max_deposit, 100_000_000);
+    assert_eq!(product.capacity, 150_000_000);
+    assert_eq!(
+        Some(product.rate_per_second),
+        annual_bps_to_per_second_ray(400, RateBasis::Apy, Compounding::Daily)
+    );
+
+    // Capacity is shared by all open boxes, and freed when they close
+    product.record_deposit(100_000_000).unwrap();
+    assert!(product.record_deposit(50_000_001).is_none());
+    product.record_deposit(50_000_000).unwrap();
+    assert_eq!(product.open_count, 2);
+    product.release_deposit(100_000_000).unwrap();
+    assert_eq!((product.total_principal, product.open_count), (50_000_000, 1));
+
+    // Capacity cannot drop below what is open, and terms are validated
+    assert!(product.set_terms(ProductTerms { capacity: 40, max_deposit: 40, ..terms.clone() }, 6).is_err());
+    assert!(product.set_terms(ProductTerms { min_deposit: 101, ..terms.clone() }, 6).is_err());
+    assert!(product.set_terms(ProductTerms { seconds_locked: 0, ..terms.clone() }, 6).is_err());
+    assert!(product.set_terms(ProductTerms { name: "x".repeat(33), ..terms }, 6).is_err());
+}
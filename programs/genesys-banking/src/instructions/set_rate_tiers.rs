@@ -0,0 +1,223 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::instructions::initialize::VaultInfo;
+use crate::instructions::set_role::{VaultRoles, RoleError};
+use crate::instructions::update_vault_config::ConfigError;
+use crate::events::RateTiersUpdated;
+use crate::constants::*;
+use crate::math::{annual_bps_to_per_second_ray, Compounding, RateBasis};
+
+
+pub fn handler(
+    ctx: Context<SetRateTiers>,
+    tiers: Vec<RateTier>,
+) -> Result<()> {
+
+    // Replace the whole table. Open deposits keep the rate they were opened with.
+    let vault_info = &ctx.accounts.vault_info;
+    let rate_tiers = &mut ctx.accounts.rate_tiers;
+    rate_tiers.vault_info = vault_info.key();
+    rate_tiers.rate_basis = vault_info.rate_basis;
+    rate_tiers.tiers = tiers;
+    rate_tiers.validate()?;
+
+    // Convert once here, so deposits just read the per-second rate of their bracket
+    rate_tiers.set_rates(vault_info.compounding)?;
+
+    emit!(RateTiersUpdated {
+        vault_info: rate_tiers.vault_info,
+        risk_manager: ctx.accounts.risk_manager.key(),
+        tiers: rate_tiers.tiers.clone(),
+        rate_basis: rate_tiers.rate_basis,
+        rates_per_second: rate_tiers.rates_per_second.clone(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(
+    info_bump: u8,
+)]
+/// This SetRateTiers context is used to publish the vault's interest rates by lock duration.
+///
+/// Requirements
+/// ----------------------
+/// 1) Only the risk manager can change the table.
+/// 2) Brackets must be sorted by their minimum lock duration, with rates within the vault's bounds.
+/// 3) Rates are annual, quoted on the vault's `rate_basis`, and converted here under its compounding convention
+///    (`SetCompounding` converts them again when the convention changes).
+/// 4) An empty table makes every deposit earn the vault's flat rate.
+pub struct SetRateTiers<'info> {
+
+    /// This account holds the metadata for the vault
+    #[account(
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = info_bump,
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    /// This account holds the roles of the vault
+    #[account(
+        seeds = [
+            VAULT_ROLES_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub vault_roles: Account<'info, VaultRoles>,
+
+    /// This account is a PDA that holds the rate table of the vault (created on first use)
+    #[account(
+        init_if_needed,
+        payer = risk_manager,
+        space = RateTiers::SPACE,
+        seeds = [
+            RATE_TIERS_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub rate_tiers: Account<'info, RateTiers>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(address=vault_info.token_mint)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// This is the risk manager
+    #[account(mut, address=vault_roles.risk_manager @ RoleError::MissingRole)]
+    pub risk_manager: Signer<'info>,
+
+    /// System Program
+    pub system_program: Program<'info, System>,
+}
+
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq)]
+/// A lock-duration bracket and the rate it earns
+pub struct RateTier {
+
+    /// Shortest lock (in seconds) earning this rate
+    pub min_seconds_locked: u64,
+
+    /// Annual interest rate of the bracket (in bps)
+    pub annual_rate_bps: u64,
+}
+
+
+#[account]
+#[derive(Default)]
+/// This struct holds the interest rates of a vault by lock duration
+pub struct RateTiers {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// Basis the bracket rates are quoted on (the vault's when the table was published)
+    pub rate_basis: RateBasis,
+
+    /// Brackets, sorted by their minimum lock duration
+    pub tiers: Vec<RateTier>,
+
+    /// Per-second rate of each bracket (scaled by `RAY`) under the vault's compounding convention
+    pub rates_per_second: Vec<u128>,
+}
+
+impl RateTiers {
+
+    /// Account size with room for `MAX_RATE_TIERS` brackets
+    pub const SPACE: usize = 8 + 32 + 1 + 4 + MAX_RATE_TIERS * (8 + 8) + 4 + MAX_RATE_TIERS * 16;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.tiers.len() <= MAX_RATE_TIERS, RateTierError::TooManyTiers);
+        require!(
+            self.tiers.windows(2).all(|pair| pair[0].min_seconds_locked < pair[1].min_seconds_locked),
+            RateTierError::UnsortedTiers
+        );
+        require!(
+            self.tiers.iter().all(|tier| tier.annual_rate_bps > 0 && tier.annual_rate_bps <= MAX_ANNUAL_RATE_BPS),
+            ConfigError::InterestRateOutOfBounds
+        );
+        Ok(())
+    }
+
+    /// Converts every bracket's annual rate into the per-second rate compounded under `compounding`
+    pub fn set_rates(&mut self, compounding: Compounding) -> Result<()> {
+        let rates_per_second: Option<Vec<u128>> = self.tiers
+            .iter()
+            .map(|tier| annual_bps_to_per_second_ray(tier.annual_rate_bps, self.rate_basis, compounding))
+            .collect();
+        require!(rates_per_second.is_some(), ConfigError::InterestRateOutOfBounds);
+        self.rates_per_second = rates_per_second.unwrap();
+        Ok(())
+    }
+
+    /// Per-second rate of the longest bracket `seconds_locked` qualifies for, if any
+    pub fn rate_for(&self, seconds_locked: u64) -> Option<u128> {
+        self.tiers
+            .iter()
+            .zip(&self.rates_per_second)
+            .rev()
+            .find(|(tier, _)| tier.min_seconds_locked <= seconds_locked)
+            .map(|(_, &rate_per_second)| rate_per_second)
+    }
+}
+
+
+#[error_code]
+pub enum RateTierError {
+    #[msg("Too many rate brackets")]
+    TooManyTiers,
+    #[msg("Rate brackets must be sorted by strictly increasing lock duration")]
+    UnsortedTiers,
+}
+
+
+#[test]
+fn test_rate_for_picks_the_longest_qualifying_bracket(){
+    let tier = |min_seconds_locked, annual_rate_bps| RateTier { min_seconds_locked, annual_rate_bps };
+    let rate_tiers = RateTiers {
+        tiers: vec![tier(60, 10), tier(3_600, 20), tier(86_400, 30)],
+        ..Default::default()
+    };
+    let mut rate_tiers = rate_tiers;
+    assert!(rate_tiers.validate().is_ok());
+    assert!(rate_tiers.set_rates(Compounding::Simple).is_ok());
+    let rate = |annual_rate_bps| annual_bps_to_per_second_ray(annual_rate_bps, RateBasis::Apr, Compounding::Simple);
+
+    // Locks shorter than every bracket fall back to the vault's flat rate
+    assert_eq!(rate_tiers.rate_for(59), None);
+    assert_eq!(rate_tiers.rate_for(60), rate(10));
+    assert_eq!(rate_tiers.rate_for(3_599), rate(10));
+    assert_eq!(rate_tiers.rate_for(3_600), rate(20));
+    assert_eq!(rate_tiers.rate_for(u64::MAX), rate(30));
+    assert_eq!(RateTiers::default().rate_for(100), None);
+
+    let unsorted = RateTiers { tiers: vec![tier(60, 10), tier(60, 20)], ..Default::default() };
+    assert!(unsorted.validate().is_err());
+    let zero_rate = RateTiers { tiers: vec![tier(60, 0)], ..Default::default() };
+    assert!(zero_rate.validate().is_err());
+    let too_many = RateTiers { tiers: (0..=MAX_RATE_TIERS as u64).map(|i| tier(i, 10)).collect(), ..Default::default() };
+    assert!(too_many.validate().is_err());
+}
+
+#[test]
+fn test_rates_follow_the_compounding_convention(){
+    let mut rate_tiers = RateTiers {
+        rate_basis: RateBasis::Apy,
+        tiers: vec![RateTier { min_seconds_locked: 0, annual_rate_bps: 500 }],
+        ..Default::default()
+    };
+    rate_tiers.set_rates(Compounding::PerSecond).unwrap();
+    let compounded = rate_tiers.rate_for(0).unwrap();
+    rate_tiers.set_rates(Compounding::Simple).unwrap();
+
+    // The same APY takes a higher per-second rate without compounding
+    assert!(rate_tiers.rate_for(0).unwrap() > compounded);
+    assert_eq!(rate_tiers.rate_for(0), annual_bps_to_per_second_ray(500, RateBasis::Apy, Compounding::Simple));
+}
@@ -0,0 +1,156 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::instructions::initialize::VaultInfo;
+use crate::events::RoleAssigned;
+use crate::constants::*;
+
+
+pub fn handler(
+    ctx: Context<SetRole>,
+    role: Role,
+    account: Pubkey,
+) -> Result<()> {
+
+    // Assign role
+    let vault_roles = &mut ctx.accounts.vault_roles;
+    match role {
+        Role::Operator => vault_roles.operator = account,
+        Role::RiskManager => vault_roles.risk_manager = account,
+        Role::Pauser => vault_roles.pauser = account,
+    }
+
+    emit!(RoleAssigned {
+        vault_info: vault_roles.vault_info,
+        role,
+        account,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(
+    info_bump: u8,
+)]
+/// This SetRole context is used by the owner (the vault admin) to assign operational roles.
+///
+/// Requirements
+/// ----------------------
+/// 1) Only the vault admin can assign roles.
+pub struct SetRole<'info> {
+
+    /// This account holds the metadata for the vault
+    #[account(
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = info_bump,
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    /// This account holds the roles of the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_ROLES_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub vault_roles: Account<'info, VaultRoles>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(address=vault_info.token_mint)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// This is the vault admin (the owner of the roles)
+    #[account(address=vault_info.vault_admin)]
+    pub vault_admin: Signer<'info>,
+}
+
+
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+/// Operational roles of a vault. Assigning them is reserved to the vault admin.
+pub enum Role {
+    /// Can refresh the reserve
+    Operator,
+    /// Can change rates and caps
+    RiskManager,
+    /// Can pause the vault
+    Pauser,
+}
+
+#[account]
+#[derive(Default)]
+/// This struct holds the accounts allowed to perform each privileged vault operation
+pub struct VaultRoles {
+
+    /// The vault these roles belong to
+    pub vault_info: Pubkey,
+
+    /// Can refresh the reserve
+    pub operator: Pubkey,
+
+    /// Can change rates and caps
+    pub risk_manager: Pubkey,
+
+    /// Can pause the vault
+    pub pauser: Pubkey,
+
+}
+
+impl VaultRoles {
+
+    /// Sets up the roles of `vault_info`, all held by `vault_admin`
+    pub fn init(&mut self, vault_info: Pubkey, vault_admin: Pubkey) {
+        self.vault_info = vault_info;
+        self.operator = vault_admin;
+        self.risk_manager = vault_admin;
+        self.pauser = vault_admin;
+    }
+
+    /// Moves every role still held by `previous_admin` to `new_admin`
+    pub fn hand_over(&mut self, previous_admin: Pubkey, new_admin: Pubkey) {
+        for holder in [&mut self.operator, &mut self.risk_manager, &mut self.pauser] {
+            if *holder == previous_admin {
+                *holder = new_admin;
+            }
+        }
+    }
+}
+
+
+#[error_code]
+pub enum RoleError {
+    #[msg("Signer does not hold the role required for this operation")]
+    MissingRole,
+}
+
+#[test]
+fn test_roles_default_to_the_admin(){
+    let (vault_info, vault_admin) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut vault_roles = VaultRoles::default();
+    vault_roles.init(vault_info, vault_admin);
+
+    assert_eq!(vault_roles.vault_info, vault_info);
+    assert_eq!(vault_roles.operator, vault_admin);
+    assert_eq!(vault_roles.risk_manager, vault_admin);
+    assert_eq!(vault_roles.pauser, vault_admin);
+}
+
+#[test]
+fn test_hand_over_keeps_delegated_roles(){
+    let (previous_admin, new_admin, pauser) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut vault_roles = VaultRoles::default();
+    vault_roles.init(Pubkey::new_unique(), previous_admin);
+    vault_roles.pauser = pauser;
+    vault_roles.hand_over(previous_admin, new_admin);
+
+    assert_eq!(vault_roles.operator, new_admin);
+    assert_eq!(vault_roles.risk_manager, new_admin);
+    assert_eq!(vault_roles.pauser, pauser);
+}
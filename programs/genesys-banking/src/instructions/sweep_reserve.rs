@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{TokenAccount, Mint, Token};
+use spl_token;
+use solana_program;
+
+use crate::instructions::initialize::VaultInfo;
+use crate::events::ReserveSwept;
+use crate::constants::*;
+
+
+pub fn handler(
+    ctx: Context<SweepReserve>,
+    info_bump: u8,
+    amount: u64,
+) -> Result<()> {
+
+    // Ensure admin is sweeping a nonzero amount
+    require!(amount > 0, SweepError::ZeroSweep);
+
+    // Never dip into interest owed to depositors, nor into the buffer, and only hand back funded
+    // tokens: minting the reserve up with `refresh_reserve` and sweeping it would let the admin mint to themselves
+    let vault_info = &mut ctx.accounts.vault_info;
+    require!(
+        amount <= vault_info.sweepable_reserve(ctx.accounts.token_vault.amount),
+        SweepError::ExceedsExcessReserve
+    );
+    vault_info.total_swept += amount;
+
+    // The vault_info PDA is namespaced by the mint
+    let mint_key = ctx.accounts.token_mint.key();
+
+    // Construct instruction using spl_token library
+    let ix = spl_token::instruction::transfer_checked(
+
+        // token_program_id: &Pubkey,
+        // source_pubkey: &Pubkey,
+        // mint_pubkey: &Pubkey,
+        // destination_pubkey: &Pubkey,
+        // authority_pubkey: &Pubkey,
+        // signer_pubkeys: &[&Pubkey],
+        // amount: u64,
+        // decimals: u8
+
+        &ctx.accounts.token_program.key(),
+        &ctx.accounts.token_vault.key(),
+        &ctx.accounts.token_mint.key(),
+        &ctx.accounts.destination.key(),
+        &ctx.accounts.vault_info.key(),
+        &[&ctx.accounts.vault_info.key()],
+        amount,
+        ctx.accounts.token_mint.decimals,
+    )?;
+
+    // Invoke using solana_program library, signing as the vault_info PDA (the reserve authority)
+    solana_program::program::invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.token_vault.to_account_info(),
+            ctx.accounts.vault_info.to_account_info(),
+            ctx.accounts.token_mint.to_account_info(),
+            ctx.accounts.destination.to_account_info(),
+        ],
+        &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[info_bump]]],
+    )?;
+
+    emit!(ReserveSwept {
+        vault_info: ctx.accounts.vault_info.key(),
+        token_vault: ctx.accounts.token_vault.key(),
+        destination: ctx.accounts.destination.key(),
+        amount,
+        reserve_balance: ctx.accounts.token_vault.amount - amount,
+        total_swept: ctx.accounts.vault_info.total_swept,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(
+    info_bump: u8,
+)]
+/// This SweepReserve context is used to move excess tokens out of the reserve.
+///
+/// Requirements
+/// ----------------------
+/// 1) Only the vault admin can sweep.
+/// 2) The reserve keeps at least the interest promised to open deposits plus vault_info.reserve_buffer.
+/// 3) Only tokens contributed through `fund_reserve` can be swept, never minted reserve.
+pub struct SweepReserve<'info> {
+
+    /// This account holds the metadata for the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = info_bump,
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    /// This token account is PDA which serves as the reserve for the SPL token
+    #[account(
+        mut,
+        address=vault_info.token_vault
+    )]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(address=vault_info.token_mint)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// This is the vault admin
+    #[account(address=vault_info.vault_admin)]
+    pub vault_admin: Signer<'info>,
+
+    /// This token account receives the swept tokens
+    #[account(
+        mut,
+        constraint = destination.mint == token_mint.key() @ SweepError::WrongMint,
+    )]
+    pub destination: Account<'info, TokenAccount>,
+
+    /// Token Program
+    pub token_program: Program<'info, Token>,
+}
+
+
+#[error_code]
+pub enum SweepError {
+    #[msg("Attempting to sweep zero tokens")]
+    ZeroSweep,
+    #[msg("Attempting to sweep tokens owed to depositors, held as buffer, or not funded")]
+    ExceedsExcessReserve,
+    #[msg("Destination token account is not for the vault's mint")]
+    WrongMint,
+}
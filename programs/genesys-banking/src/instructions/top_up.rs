@@ -0,0 +1,247 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{TokenAccount, Mint, Token};
+use spl_token;
+use solana_program;
+
+use crate::instructions::initialize::VaultInfo;
+use crate::instructions::deposit::DepositInfo;
+use crate::events::ToppedUp;
+use crate::constants::*;
+use crate::math::compounded_balance_fits;
+
+
+pub fn handler(
+    ctx: Context<TopUp>,
+    vault_info_bump: u8,
+    top_up_lamports: u64,
+) -> Result<()> {
+
+    // Ensure deposits are open (a top-up also pays out accrued interest)
+    let vault_info = &ctx.accounts.vault_info;
+    require!(!vault_info.deposits_paused && !vault_info.emergency_mode, TopUpError::DepositsPaused);
+
+    // Product boxes are fixed term certificates
+    require!(ctx.accounts.deposit_info.product.is_none(), TopUpError::ProductBox);
+
+    // Ensure user is adding a nonzero amount
+    require!(top_up_lamports > 0, TopUpError::ZeroTopUp);
+
+    // The vault_info PDA is namespaced by the mint
+    let mint_key = ctx.accounts.token_mint.key();
+
+    // Blend the box: credit accrued interest, add the top-up and restart the lock
+    let vault_info = &mut ctx.accounts.vault_info;
+    let deposit_info = &mut ctx.accounts.deposit_info;
+    let previous_principal = deposit_info.deposit_lamports;
+    let previous_promise = deposit_info.compute_interest();
+    let accrued = deposit_info.apply_top_up(top_up_lamports, Clock::get()?.unix_timestamp);
+    require!(accrued.is_some(), TopUpError::MaxDepositLimit);
+    let accrued = accrued.unwrap();
+    let promise = deposit_info.compute_interest();
+    require!(promise.is_some() && previous_promise.is_some(), TopUpError::BreakingTheBank);
+
+    // Ensure the blended box respects the same limits as a fresh deposit
+    require!(deposit_info.deposit_lamports <= vault_info.max_user_deposit, TopUpError::MaxDepositLimit);
+    require!(
+        compounded_balance_fits(
+            deposit_info.deposit_lamports,
+            deposit_info.rate_per_second,
+            deposit_info.seconds_locked,
+            vault_info.max_tokens,
+            deposit_info.compounding,
+        ),
+        TopUpError::BreakingTheBank
+    );
+
+    // Check if reserve vault has enough to credit the accrued interest
+    require!(ctx.accounts.token_vault.amount >= accrued, TopUpError::NotEnoughTokensInReserve);
+
+    // Swap the box's old promise for the blended one, and ensure what is left in
+    // the reserve after crediting the accrued interest still covers all promises
+    require!(
+        vault_info.release_deposit(previous_principal, previous_promise.unwrap()).is_some()
+            && vault_info.record_deposit(deposit_info.deposit_lamports, promise.unwrap()).is_some(),
+        TopUpError::BreakingTheBank
+    );
+    require!(
+        vault_info.is_solvent(ctx.accounts.token_vault.amount - accrued),
+        TopUpError::ReserveCannotCoverPromises
+    );
+
+    // First, move the accrued interest from the reserve into the user vault
+    if accrued > 0 {
+        let ix = spl_token::instruction::transfer_checked(
+
+            // token_program_id: &Pubkey,
+            // source_pubkey: &Pubkey,
+            // mint_pubkey: &Pubkey,
+            // destination_pubkey: &Pubkey,
+            // authority_pubkey: &Pubkey,
+            // signer_pubkeys: &[&Pubkey],
+            // amount: u64,
+            // decimals: u8
+
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.token_vault.key(),
+            &ctx.accounts.token_mint.key(),
+            &ctx.accounts.user_vault.key(),
+            &ctx.accounts.vault_info.key(),
+            &[&ctx.accounts.vault_info.key()],
+            accrued,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Invoke using solana_program library
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.token_vault.to_account_info(),
+                ctx.accounts.vault_info.to_account_info(),
+                ctx.accounts.token_mint.to_account_info(),
+                ctx.accounts.user_vault.to_account_info(),
+            ],
+            &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[vault_info_bump]]]
+        )?;
+    }
+
+    // Second, move the top-up from the user into the user vault
+    let ix = spl_token::instruction::transfer_checked(
+        &ctx.accounts.token_program.key(),
+        &ctx.accounts.depositor_token_account.key(),
+        &ctx.accounts.token_mint.key(),
+        &ctx.accounts.user_vault.key(),
+        &ctx.accounts.depositor.key(),
+        &[&ctx.accounts.depositor.key()],
+        top_up_lamports,
+        ctx.accounts.token_mint.decimals,
+    )?;
+
+    // Invoke using solana_program library
+    solana_program::program::invoke(
+        &ix,
+        &[
+            ctx.accounts.depositor_token_account.to_account_info(),
+            ctx.accounts.depositor.to_account_info(),
+            ctx.accounts.token_mint.to_account_info(),
+            ctx.accounts.user_vault.to_account_info(),
+        ],
+    )?;
+
+    let deposit_info = &ctx.accounts.deposit_info;
+    emit!(ToppedUp {
+        vault_info: ctx.accounts.vault_info.key(),
+        depositor: ctx.accounts.depositor.key(),
+        deposit_info: deposit_info.key(),
+        deposit_index: deposit_info.deposit_index,
+        top_up_lamports,
+        accrued_interest: accrued,
+        deposit_lamports: deposit_info.deposit_lamports,
+        promised_interest: promise.unwrap(),
+        timestamp: deposit_info.deposit_time,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(
+    reserve_bump: u8,
+    vault_info_bump: u8,
+    deposit_info_bump: u8,
+    user_vault_bump: u8,
+    deposit_index: u64,
+)]
+/// This TopUp context is used to add tokens to an open deposit box.
+///
+/// Requirements
+/// ----------------------
+/// 1) The box must belong to the depositor and still be open.
+/// 2) The lock restarts with a blended principal (see `DepositInfo::apply_top_up`).
+pub struct TopUp<'info> {
+
+    /// This account holds the metadata for the deposit box being topped up
+    #[account(
+        mut,
+        seeds = [
+            USER_DEPOSIT_INFO.as_bytes(),
+            &vault_info.key().to_bytes(),
+            &depositor.key.to_bytes(),
+            &deposit_index.to_le_bytes(),
+        ],
+        bump = deposit_info_bump,
+    )]
+    pub deposit_info: Box<Account<'info, DepositInfo>>,
+
+    /// This account holds the metadata for the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = vault_info_bump
+    )]
+    pub vault_info: Box<Account<'info, VaultInfo>>,
+
+    /// This token account serves as the account which holds the SPL token
+    #[account(
+        mut,
+        seeds = [
+            USER_VAULT_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+            &depositor.key.to_bytes(),
+            &deposit_index.to_le_bytes(),
+        ],
+        bump = user_vault_bump,
+    )]
+    pub user_vault: Box<Account<'info, TokenAccount>>,
+
+    /// This token account is PDA which serves as the reserve for the SPL token
+    #[account(
+        mut,
+        seeds = [
+            TOKEN_VAULT_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = reserve_bump,
+    )]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(address=vault_info.token_mint)]
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    /// This account is the user/depositor
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// This account is the user's SPL token account
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = depositor,
+    )]
+    pub depositor_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Token Program
+    pub token_program: Program<'info, Token>,
+}
+
+
+#[error_code]
+pub enum TopUpError {
+    #[msg("Attempting to top up with zero tokens")]
+    ZeroTopUp,
+    #[msg("Attempting to grow a deposit box over the vault's deposit limit")]
+    MaxDepositLimit,
+    #[msg("Attempting to top up to an amount that would break the bank")]
+    BreakingTheBank,
+    #[msg("The reserve does not have enough tokens to credit accrued interest right now")]
+    NotEnoughTokensInReserve,
+    #[msg("The reserve cannot cover the interest promised to all depositors")]
+    ReserveCannotCoverPromises,
+    #[msg("Deposits are paused")]
+    DepositsPaused,
+    #[msg("Boxes opened through a product cannot be topped up")]
+    ProductBox,
+}
@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::instructions::initialize::{VaultInfo, VaultConfig};
+use crate::instructions::set_role::{VaultRoles, RoleError};
+use crate::events::ConfigUpdated;
+use crate::constants::*;
+
+
+pub fn handler(
+    ctx: Context<UpdateVaultConfig>,
+    config: VaultConfig,
+) -> Result<()> {
+
+    // Update vault parameters (caps are given in whole tokens). Open deposits keep the
+    // rate and penalty they were opened with, since they are stored in their DepositInfo.
+    let vault_info = &mut ctx.accounts.vault_info;
+    vault_info.apply_config(&config)?;
+
+    // The cap cannot drop below the interest already promised to open deposits or queued for withdrawn ones
+    let liabilities = vault_info.liabilities();
+    require!(liabilities.is_some(), ConfigError::CapOverflow);
+    require!(
+        vault_info.max_tokens >= liabilities.unwrap(),
+        ConfigError::MaxTokensBelowLiabilities
+    );
+
+    emit!(ConfigUpdated {
+        vault_info: vault_info.key(),
+        risk_manager: ctx.accounts.risk_manager.key(),
+        max_tokens: vault_info.max_tokens,
+        annual_rate_bps: vault_info.annual_rate_bps,
+        rate_basis: vault_info.rate_basis,
+        rate_per_second: vault_info.rate_per_second,
+        post_maturity_rate_bps: vault_info.post_maturity_rate_bps,
+        max_post_maturity_seconds: vault_info.max_post_maturity_seconds,
+        max_user_deposit: vault_info.max_user_deposit,
+        early_withdrawal_penalty_bps: vault_info.early_withdrawal_penalty_bps,
+        reserve_buffer: vault_info.reserve_buffer,
+        products_only: vault_info.products_only,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(
+    info_bump: u8,
+)]
+/// This UpdateVaultConfig context is used to change the parameters of an initialized vault.
+///
+/// Requirements
+/// ----------------------
+/// 1) Only the risk manager can update the configuration.
+/// 2) New values are validated (see `VaultInfo::validate_config`).
+pub struct UpdateVaultConfig<'info> {
+
+    /// This account holds the metadata for the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = info_bump,
+    )]
+    pub vault_info: Account<'info, VaultInfo>,
+
+    /// This account holds the roles of the vault
+    #[account(
+        seeds = [
+            VAULT_ROLES_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub vault_roles: Account<'info, VaultRoles>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(address=vault_info.token_mint)]
+    pub token_mint: Account<'info, Mint>,
+
+    /// This is the risk manager
+    #[account(address=vault_roles.risk_manager @ RoleError::MissingRole)]
+    pub risk_manager: Signer<'info>,
+}
+
+
+#[error_code]
+pub enum ConfigError {
+    #[msg("Reserve maximum must be nonzero")]
+    ZeroMaxTokens,
+    #[msg("Interest rate is out of bounds")]
+    InterestRateOutOfBounds,
+    #[msg("Deposit limit must be nonzero and at most the reserve maximum")]
+    InvalidMaxUserDeposit,
+    #[msg("Reserve maximum cannot be below the interest promised or queued to depositors")]
+    MaxTokensBelowLiabilities,
+    #[msg("Early withdrawal penalty cannot exceed 100%")]
+    PenaltyOutOfBounds,
+    #[msg("Caps do not fit in base units of the token mint")]
+    CapOverflow,
+}
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use anchor_lang::AccountsClose;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{TokenAccount, Mint, Token}
@@ -8,6 +9,8 @@ use solana_program;
 
 use crate::instructions::initialize::VaultInfo;
 use crate::instructions::deposit::DepositInfo;
+use crate::instructions::deposit_product::release_from_product;
+use crate::events::{Withdrawn, ClaimSettled};
 use crate::constants::*;
 
 
@@ -19,18 +22,97 @@ pub fn handler(
     // Grab deposit info
     let deposit_info = &ctx.accounts.deposit_info;
 
+    // The vault_info PDA is namespaced by the mint
+    let mint_key = ctx.accounts.token_mint.key();
+
+    // Ensure withdrawals are open, and interest payouts are not frozen
+    require!(!ctx.accounts.vault_info.withdrawals_paused, WithdrawError::WithdrawalsPaused);
+    require!(!ctx.accounts.vault_info.emergency_mode, WithdrawError::EmergencyMode);
+
     // Check if user has waited enough time
-    msg!("It's been {} units of time since deposit", deposit_info.get_elapsed());
     require!(deposit_info.after_lockout(), WithdrawError::TooSoon);
 
-    // Check if reserve vault has enough to pay user
-    let user_payout = deposit_info.compute_interest();
-    require!(ctx.accounts.token_vault.amount >= user_payout, WithdrawError::NotEnoughTokensInReserve);
+    // Interest is earned on the time actually elapsed, including any post-maturity accrual
+    let interest = deposit_info.interest_after(deposit_info.get_elapsed() as u64);
+    let promised_interest = deposit_info.compute_interest();
+    require!(interest.is_some() && promised_interest.is_some(), WithdrawError::InterestOverflow);
+
+    // Pay as much interest as the reserve holds beyond the claims already queued, which come first.
+    // The principal always goes back right away.
+    let interest = interest.unwrap();
+    let payable = ctx.accounts.token_vault.amount.saturating_sub(ctx.accounts.vault_info.total_queued_interest);
+    let user_payout = interest.min(payable);
+    let queued_interest = interest - user_payout;
+
+    // This box no longer counts towards the vault's liabilities, which recorded the most it could earn
+    require!(
+        ctx.accounts.vault_info.release_deposit(deposit_info.deposit_lamports, promised_interest.unwrap()).is_some(),
+        WithdrawError::LiabilitiesUnderflow
+    );
+    // ...and from its product, if it was opened through one
+    release_from_product(ctx.program_id, ctx.remaining_accounts, deposit_info)?;
+
+    // The rest of the interest is owed in queue order, and is settled as the reserve is replenished
+    if queued_interest > 0 {
+        let sequence = ctx.accounts.vault_info.queue_interest(queued_interest);
+        require!(sequence.is_some(), WithdrawError::InterestOverflow);
+        create_queued_claim(
+            &ctx.accounts.queued_claim,
+            &ctx.accounts.depositor,
+            &ctx.accounts.system_program,
+            ctx.program_id,
+            &QueuedClaim {
+                vault_info: ctx.accounts.vault_info.key(),
+                sequence: sequence.unwrap(),
+                claimant: ctx.accounts.depositor.key(),
+                token_account: ctx.accounts.depositor_token_account.key(),
+                amount: queued_interest,
+                queued_at: Clock::get()?.unix_timestamp,
+            },
+        )?;
+    }
 
     require!(*ctx.program_id == ctx.accounts.program.key(), WithdrawError::InvalidProgramId);
     // First, put payout tokens in user vault
-    // Construct instruction using spl_token library
-    msg!("transferring from reserve to user vault");
+    if user_payout > 0 {
+        // Construct instruction using spl_token library
+        let ix = spl_token::instruction::transfer_checked(
+
+            // token_program_id: &Pubkey, 
+            // source_pubkey: &Pubkey, 
+            // mint_pubkey: &Pubkey, 
+            // destination_pubkey: &Pubkey, 
+            // authority_pubkey: &Pubkey, 
+            // signer_pubkeys: &[&Pubkey], 
+            // amount: u64, 
+            // decimals: u8
+
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.token_vault.key(),
+            &ctx.accounts.token_mint.key(),
+            &ctx.accounts.user_vault.key(),
+            &ctx.accounts.vault_info.key(),
+            &[&ctx.accounts.vault_info.key()],
+            user_payout,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Invoke using solana_program library
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                //ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.token_vault.to_account_info(),
+                ctx.accounts.vault_info.to_account_info(),
+                ctx.accounts.token_mint.to_account_info(),
+                ctx.accounts.user_vault.to_account_info(),
+                ctx.accounts.vault_admin.to_account_info(),
+            ],
+            &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[vault_info_bump]]]
+        ).expect("failed invoking spl transfer");
+    }
+
+    // Second, return all tokens in user vault to user
     let ix = spl_token::instruction::transfer_checked(
 
         // token_program_id: &Pubkey, 
@@ -43,68 +125,69 @@ pub fn handler(
         // decimals: u8
 
         &ctx.accounts.token_program.key(),
-        &ctx.accounts.token_vault.key(),
-        &ctx.accounts.token_mint.key(),
         &ctx.accounts.user_vault.key(),
+        &ctx.accounts.token_mint.key(),
+        &ctx.accounts.depositor_token_account.key(),
         &ctx.accounts.vault_info.key(),
         &[&ctx.accounts.vault_info.key()],
-        user_payout,
+        deposit_info.deposit_lamports + user_payout,
         ctx.accounts.token_mint.decimals,
     )?;
 
-    msg!("invoking for transfer from reserve to user vault");
     // Invoke using solana_program library
     solana_program::program::invoke_signed(
         &ix,
         &[
             //ctx.accounts.token_program.to_account_info(),
-            ctx.accounts.token_vault.to_account_info(),
+            ctx.accounts.user_vault.to_account_info(),
             ctx.accounts.vault_info.to_account_info(),
             ctx.accounts.token_mint.to_account_info(),
-            ctx.accounts.user_vault.to_account_info(),
+            ctx.accounts.depositor_token_account.to_account_info(),
             ctx.accounts.vault_admin.to_account_info(),
         ],
-        &[&[VAULT_INFO_SEED.as_bytes(), &[vault_info_bump]]]
-    ).expect("failed invoking spl transfer");
+        &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[vault_info_bump]]]
+    )?;
 
-    msg!("transferring from user vault to user");
-    // Second, return all tokens in user vault to user
-    let ix = spl_token::instruction::transfer_checked(
+    // Third, close the (now empty) user vault and return its rent to the user
+    let ix = spl_token::instruction::close_account(
 
         // token_program_id: &Pubkey, 
-        // source_pubkey: &Pubkey, 
-        // mint_pubkey: &Pubkey, 
+        // account_pubkey: &Pubkey, 
         // destination_pubkey: &Pubkey, 
-        // authority_pubkey: &Pubkey, 
-        // signer_pubkeys: &[&Pubkey], 
-        // amount: u64, 
-        // decimals: u8
+        // owner_pubkey: &Pubkey, 
+        // signer_pubkeys: &[&Pubkey]
 
         &ctx.accounts.token_program.key(),
         &ctx.accounts.user_vault.key(),
-        &ctx.accounts.token_mint.key(),
-        &ctx.accounts.depositor_token_account.key(),
+        &ctx.accounts.depositor.key(),
         &ctx.accounts.vault_info.key(),
         &[&ctx.accounts.vault_info.key()],
-        deposit_info.deposit_lamports + user_payout,
-        ctx.accounts.token_mint.decimals,
     )?;
 
-    msg!("invoking for transfer from user vault to user");
     // Invoke using solana_program library
     solana_program::program::invoke_signed(
         &ix,
         &[
-            //ctx.accounts.token_program.to_account_info(),
             ctx.accounts.user_vault.to_account_info(),
+            ctx.accounts.depositor.to_account_info(),
             ctx.accounts.vault_info.to_account_info(),
-            ctx.accounts.token_mint.to_account_info(),
-            ctx.accounts.depositor_token_account.to_account_info(),
-            ctx.accounts.vault_admin.to_account_info(),
         ],
-        &[&[VAULT_INFO_SEED.as_bytes(), &[vault_info_bump]]]
+        &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[vault_info_bump]]]
     )?;
 
+    emit!(Withdrawn {
+        vault_info: ctx.accounts.vault_info.key(),
+        depositor: ctx.accounts.depositor.key(),
+        deposit_info: deposit_info.key(),
+        deposit_index: deposit_info.deposit_index,
+        deposit_lamports: deposit_info.deposit_lamports,
+        interest: user_payout,
+        queued_interest,
+        rate_per_second: deposit_info.rate_per_second,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    // deposit_info is closed to the depositor by its `close` constraint
 
     Ok(())
 }
@@ -117,20 +200,26 @@ pub fn handler(
     vault_info_bump: u8,
     deposit_info_bump: u8,
     user_vault_bump: u8,
+    deposit_index: u64,
 )]
 /// This InitializeVault context is used to initialize the bank vault which holds a reserve of some SPL token.
 /// 
 /// Requirements
 /// ----------------------
-/// 1) It should hold up to 10,000,000 of a custom SPL token.
+/// 1) It should hold up to vault_info.max_tokens of a custom SPL token.
 /// 2) Only some admin should have the authority to mint + deposit this SPL token.
 pub struct Withdraw<'info> {
 
-    /// This account holds the metadata for the vault
+    /// This account holds the metadata for the deposit box being withdrawn
+    /// (closed on withdraw, rent goes back to the depositor)
     #[account(
+        mut,
+        close = depositor,
         seeds = [
             USER_DEPOSIT_INFO.as_bytes(), 
+            &vault_info.key().to_bytes(),
             &depositor.key.to_bytes(),
+            &deposit_index.to_le_bytes(),
         ],
         bump = deposit_info_bump,
     )]
@@ -138,17 +227,24 @@ pub struct Withdraw<'info> {
 
     /// This account holds the metadata for the vault
     #[account(
-        seeds = [VAULT_INFO_SEED.as_bytes()],
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
         bump = vault_info_bump
     )]
     pub vault_info: Box<Account<'info, VaultInfo>>,
 
     /// This token account serves as the account which holds the SPL token
+    /// (closed on withdraw, rent goes back to the depositor)
     #[account(
         mut,
         seeds = [
             USER_VAULT_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
             &depositor.key.to_bytes(),
+            &deposit_index.to_le_bytes(),
         ],
         bump = user_vault_bump,
     )]
@@ -161,6 +257,11 @@ pub struct Withdraw<'info> {
     )]
     pub token_vault: Box<Account<'info, TokenAccount>>,
 
+    /// This account is the PDA of the next `QueuedClaim`, created only if the reserve cannot pay all the interest
+    /// CHECK: This is fine because it is checked against `vault_info.queue_tail` when used (see `create_queued_claim`)
+    #[account(mut)]
+    pub queued_claim: UncheckedAccount<'info>,
+
     /// This mint account holds the mint info of the SPL token
     #[account(address=vault_info.token_mint)]
     pub token_mint: Box<Account<'info, Mint>>,
@@ -208,5 +309,215 @@ pub enum WithdrawError {
     #[msg("The reserve does not have enough tokens to pay you right now")]
     NotEnoughTokensInReserve,
     #[msg("Passed in wrong program_id")]
-    InvalidProgramId
-}
\ No newline at end of file
+    InvalidProgramId,
+    #[msg("Interest owed does not fit in a token amount")]
+    InterestOverflow,
+    #[msg("Vault liabilities are smaller than this deposit box")]
+    LiabilitiesUnderflow,
+    #[msg("Withdrawals are paused")]
+    WithdrawalsPaused,
+    #[msg("Interest payouts are frozen, use the emergency withdraw to recover your principal")]
+    EmergencyMode,
+    #[msg("The queued claim account is not the next claim of this vault")]
+    WrongQueuedClaim,
+    #[msg("Queued claims must be settled in order, starting from the oldest")]
+    ClaimOutOfOrder,
+    #[msg("Token account or claimant does not match the queued claim")]
+    ClaimAccountMismatch,
+}
+
+
+/// Creates the `QueuedClaim` account of `claim.sequence`, with the depositor paying its rent.
+///
+/// The account is funded, allocated and assigned in separate steps, so lamports sent to
+/// its address beforehand cannot hold back the principal of a withdrawal.
+fn create_queued_claim<'info>(
+    queued_claim: &UncheckedAccount<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    program_id: &Pubkey,
+    claim: &QueuedClaim,
+) -> Result<()> {
+
+    let sequence = claim.sequence.to_le_bytes();
+    let (address, bump) = Pubkey::find_program_address(
+        &[QUEUED_CLAIM_SEED.as_bytes(), claim.vault_info.as_ref(), &sequence],
+        program_id,
+    );
+    require!(queued_claim.key() == address, WithdrawError::WrongQueuedClaim);
+    let signer_seeds: &[&[u8]] = &[QUEUED_CLAIM_SEED.as_bytes(), claim.vault_info.as_ref(), &sequence, &[bump]];
+
+    let claim_info = queued_claim.to_account_info();
+    let rent = Rent::get()?.minimum_balance(QueuedClaim::SPACE).saturating_sub(claim_info.lamports());
+    if rent > 0 {
+        let ix = solana_program::system_instruction::transfer(&payer.key(), &address, rent);
+        solana_program::program::invoke(
+            &ix,
+            &[
+                payer.to_account_info(),
+                claim_info.clone(),
+                system_program.to_account_info(),
+            ],
+        )?;
+    }
+    for ix in [
+        solana_program::system_instruction::allocate(&address, QueuedClaim::SPACE as u64),
+        solana_program::system_instruction::assign(&address, program_id),
+    ] {
+        solana_program::program::invoke_signed(
+            &ix,
+            &[claim_info.clone(), system_program.to_account_info()],
+            &[signer_seeds],
+        )?;
+    }
+
+    let mut data = claim_info.try_borrow_mut_data()?;
+    claim.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
+
+
+/// Pays queued interest out of the reserve, oldest claim first, and returns the amount paid.
+///
+/// `remaining_accounts` holds, for each claim to settle in queue order from `vault_info.queue_head`,
+/// its `QueuedClaim`, the token account it is paid to and the claimant, who gets the claim's rent back
+/// once it is paid off. Settlement stops when the accounts, the claims or the reserve run out, and
+/// does nothing while the vault is in emergency mode.
+pub fn settle_queued_claims<'info>(
+    program_id: &Pubkey,
+    remaining_accounts: &[AccountInfo<'info>],
+    vault_info: &mut Account<'info, VaultInfo>,
+    token_vault: &mut Account<'info, TokenAccount>,
+    token_mint: &Account<'info, Mint>,
+    token_program: &Program<'info, Token>,
+    info_bump: u8,
+) -> Result<u64> {
+
+    if vault_info.emergency_mode {
+        return Ok(0);
+    }
+
+    let mint_key = token_mint.key();
+    let mut available = token_vault.amount;
+    let mut settled = 0;
+    for accounts in remaining_accounts.chunks(3) {
+        if available == 0 || vault_info.queue_head == vault_info.queue_tail {
+            break;
+        }
+        require!(accounts.len() == 3, WithdrawError::ClaimAccountMismatch);
+        let (claim_info, claimant_token_account, claimant) = (&accounts[0], &accounts[1], &accounts[2]);
+
+        let mut claim = Account::<QueuedClaim>::try_from(claim_info)?;
+        require!(
+            claim.vault_info == vault_info.key() && claim.sequence == vault_info.queue_head,
+            WithdrawError::ClaimOutOfOrder
+        );
+        require!(
+            claimant_token_account.key() == claim.token_account && claimant.key() == claim.claimant,
+            WithdrawError::ClaimAccountMismatch
+        );
+
+        let amount = claim.settle(available);
+        let ix = spl_token::instruction::transfer_checked(
+            &token_program.key(),
+            &token_vault.key(),
+            &token_mint.key(),
+            &claimant_token_account.key(),
+            &vault_info.key(),
+            &[&vault_info.key()],
+            amount,
+            token_mint.decimals,
+        )?;
+
+        // Invoke using solana_program library
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                token_vault.to_account_info(),
+                vault_info.to_account_info(),
+                token_mint.to_account_info(),
+                claimant_token_account.clone(),
+            ],
+            &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[info_bump]]]
+        )?;
+
+        available -= amount;
+        settled += amount;
+        let paid_off = claim.amount == 0;
+        require!(vault_info.settle_queued_interest(amount, paid_off).is_some(), WithdrawError::LiabilitiesUnderflow);
+
+        emit!(ClaimSettled {
+            vault_info: vault_info.key(),
+            sequence: claim.sequence,
+            claimant: claim.claimant,
+            token_account: claim.token_account,
+            amount,
+            remaining: claim.amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        // A paid off claim leaves the queue, and its rent goes back to the claimant
+        if paid_off {
+            claim.close(claimant.clone())?;
+        } else {
+            claim.exit(program_id)?;
+        }
+    }
+
+    token_vault.reload()?;
+    Ok(settled)
+}
+
+
+#[account]
+#[derive(Default, Debug, PartialEq)]
+/// This struct holds interest owed to a withdrawn deposit box which the reserve could not pay yet.
+/// Claims are numbered in the order they were queued, and are paid in that order.
+pub struct QueuedClaim {
+
+    /// The vault
+    pub vault_info: Pubkey,
+
+    /// Position of the claim in the vault's queue (see `VaultInfo::queue_head`)
+    pub sequence: u64,
+
+    /// The depositor
+    pub claimant: Pubkey,
+
+    /// The token account the interest is paid to
+    pub token_account: Pubkey,
+
+    /// Interest still owed
+    pub amount: u64,
+
+    /// Time queued
+    pub queued_at: i64,
+}
+
+impl QueuedClaim {
+
+    /// Size of the account, discriminator included
+    pub const SPACE: usize = 8 + 32 + 8 + 32 + 32 + 8 + 8;
+
+    /// Pays up to `available` towards the claim, and returns the amount paid
+    pub fn settle(&mut self, available: u64) -> u64 {
+        let amount = self.amount.min(available);
+        self.amount -= amount;
+        amount
+    }
+}
+
+
+#[test]
+fn test_queued_claim_is_paid_in_part(){
+    let mut claim = QueuedClaim { amount: 300, ..Default::default() };
+
+    // A short reserve pays what it holds, and the rest stays owed
+    assert_eq!(claim.settle(100), 100);
+    assert_eq!(claim.amount, 200);
+    assert_eq!(claim.settle(1_000), 200);
+    assert_eq!(claim.amount, 0);
+    assert_eq!(claim.settle(1_000), 0);
+
+    assert_eq!(claim.try_to_vec().unwrap().len() + 8, QueuedClaim::SPACE);
+}
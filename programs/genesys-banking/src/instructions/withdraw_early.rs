@@ -0,0 +1,263 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{TokenAccount, Mint, Token}
+};
+use spl_token;
+use solana_program;
+
+use crate::instructions::initialize::VaultInfo;
+use crate::instructions::deposit::DepositInfo;
+use crate::instructions::deposit_product::release_from_product;
+use crate::events::EarlyWithdrawn;
+use crate::constants::*;
+
+
+pub fn handler(
+    ctx: Context<WithdrawEarly>,
+    vault_info_bump: u8,
+) -> Result<()> {
+
+    // Grab deposit info
+    let deposit_info = &ctx.accounts.deposit_info;
+
+    // The vault_info PDA is namespaced by the mint
+    let mint_key = ctx.accounts.token_mint.key();
+
+    // Ensure withdrawals are open
+    require!(!ctx.accounts.vault_info.withdrawals_paused, WithdrawEarlyError::WithdrawalsPaused);
+
+    // Matured boxes go through the regular withdraw, which pays interest
+    require!(!deposit_info.after_lockout(), WithdrawEarlyError::AlreadyMatured);
+
+    // No interest is paid (interest credited by top-ups goes back too), and part of the principal
+    // is forfeited to the reserve
+    let promised_interest = deposit_info.compute_interest();
+    require!(promised_interest.is_some(), WithdrawEarlyError::InterestOverflow);
+    let penalty = deposit_info.early_withdrawal_penalty();
+    let forfeited_interest = deposit_info.credited_interest;
+    let forfeit = penalty + forfeited_interest;
+    let payout = deposit_info.deposit_lamports - forfeit;
+
+    // This box no longer counts towards the vault's liabilities
+    require!(
+        ctx.accounts.vault_info.release_deposit(deposit_info.deposit_lamports, promised_interest.unwrap()).is_some(),
+        WithdrawEarlyError::LiabilitiesUnderflow
+    );
+    // ...and from its product, if it was opened through one
+    release_from_product(ctx.program_id, ctx.remaining_accounts, deposit_info)?;
+
+    // First, move the penalty and credited interest from the user vault into the reserve
+    if forfeit > 0 {
+        let ix = spl_token::instruction::transfer_checked(
+
+            // token_program_id: &Pubkey,
+            // source_pubkey: &Pubkey,
+            // mint_pubkey: &Pubkey,
+            // destination_pubkey: &Pubkey,
+            // authority_pubkey: &Pubkey,
+            // signer_pubkeys: &[&Pubkey],
+            // amount: u64,
+            // decimals: u8
+
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.user_vault.key(),
+            &ctx.accounts.token_mint.key(),
+            &ctx.accounts.token_vault.key(),
+            &ctx.accounts.vault_info.key(),
+            &[&ctx.accounts.vault_info.key()],
+            forfeit,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Invoke using solana_program library
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.user_vault.to_account_info(),
+                ctx.accounts.vault_info.to_account_info(),
+                ctx.accounts.token_mint.to_account_info(),
+                ctx.accounts.token_vault.to_account_info(),
+            ],
+            &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[vault_info_bump]]]
+        )?;
+    }
+
+    // Second, return the rest of the principal to the user
+    if payout > 0 {
+        let ix = spl_token::instruction::transfer_checked(
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.user_vault.key(),
+            &ctx.accounts.token_mint.key(),
+            &ctx.accounts.depositor_token_account.key(),
+            &ctx.accounts.vault_info.key(),
+            &[&ctx.accounts.vault_info.key()],
+            payout,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Invoke using solana_program library
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.user_vault.to_account_info(),
+                ctx.accounts.vault_info.to_account_info(),
+                ctx.accounts.token_mint.to_account_info(),
+                ctx.accounts.depositor_token_account.to_account_info(),
+            ],
+            &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[vault_info_bump]]]
+        )?;
+    }
+
+    // Third, close the (now empty) user vault and return its rent to the user
+    let ix = spl_token::instruction::close_account(
+
+        // token_program_id: &Pubkey,
+        // account_pubkey: &Pubkey,
+        // destination_pubkey: &Pubkey,
+        // owner_pubkey: &Pubkey,
+        // signer_pubkeys: &[&Pubkey]
+
+        &ctx.accounts.token_program.key(),
+        &ctx.accounts.user_vault.key(),
+        &ctx.accounts.depositor.key(),
+        &ctx.accounts.vault_info.key(),
+        &[&ctx.accounts.vault_info.key()],
+    )?;
+
+    // Invoke using solana_program library
+    solana_program::program::invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.user_vault.to_account_info(),
+            ctx.accounts.depositor.to_account_info(),
+            ctx.accounts.vault_info.to_account_info(),
+        ],
+        &[&[VAULT_INFO_SEED.as_bytes(), mint_key.as_ref(), &[vault_info_bump]]]
+    )?;
+
+    emit!(EarlyWithdrawn {
+        vault_info: ctx.accounts.vault_info.key(),
+        depositor: ctx.accounts.depositor.key(),
+        deposit_info: deposit_info.key(),
+        deposit_index: deposit_info.deposit_index,
+        deposit_lamports: deposit_info.deposit_lamports,
+        penalty,
+        forfeited_interest,
+        payout,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    // deposit_info is closed to the depositor by its `close` constraint
+
+    Ok(())
+}
+
+
+
+#[derive(Accounts)]
+#[instruction(
+    reserve_bump: u8,
+    vault_info_bump: u8,
+    deposit_info_bump: u8,
+    user_vault_bump: u8,
+    deposit_index: u64,
+)]
+/// This WithdrawEarly context is used to break a deposit box before its lockup time is over.
+///
+/// Requirements
+/// ----------------------
+/// 1) The box must belong to the depositor and must not have matured yet.
+/// 2) No interest is paid, and the penalty locked in at deposit time goes to the reserve.
+/// 3) Interest credited into the box by top-ups is not principal, and goes back to the reserve.
+pub struct WithdrawEarly<'info> {
+
+    /// This account holds the metadata for the deposit box being withdrawn
+    /// (closed on withdraw, rent goes back to the depositor)
+    #[account(
+        mut,
+        close = depositor,
+        seeds = [
+            USER_DEPOSIT_INFO.as_bytes(),
+            &vault_info.key().to_bytes(),
+            &depositor.key.to_bytes(),
+            &deposit_index.to_le_bytes(),
+        ],
+        bump = deposit_info_bump,
+    )]
+    pub deposit_info: Box<Account<'info, DepositInfo>>,
+
+    /// This account holds the metadata for the vault
+    #[account(
+        mut,
+        seeds = [
+            VAULT_INFO_SEED.as_bytes(),
+            &token_mint.key().to_bytes(),
+        ],
+        bump = vault_info_bump
+    )]
+    pub vault_info: Box<Account<'info, VaultInfo>>,
+
+    /// This token account serves as the account which holds the SPL token
+    /// (closed on withdraw, rent goes back to the depositor)
+    #[account(
+        mut,
+        seeds = [
+            USER_VAULT_SEED.as_bytes(),
+            &vault_info.key().to_bytes(),
+            &depositor.key.to_bytes(),
+            &deposit_index.to_le_bytes(),
+        ],
+        bump = user_vault_bump,
+    )]
+    pub user_vault: Box<Account<'info, TokenAccount>>,
+
+    /// This token account is PDA which serves as the reserve for the SPL token
+    #[account(
+        mut,
+        address=vault_info.token_vault
+    )]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    /// This mint account holds the mint info of the SPL token
+    #[account(address=vault_info.token_mint)]
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    /// This account is the user/depositor
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// This account is the user's SPL token account
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        associated_token::mint = token_mint,
+        associated_token::authority = depositor,
+    )]
+    pub depositor_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// System Program
+    pub system_program: Program<'info, System>,
+
+    /// Token Program
+    pub token_program: Program<'info, Token>,
+
+    /// Token Program
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// Rent Program
+    pub rent: Sysvar<'info, Rent>,
+}
+
+
+#[error_code]
+pub enum WithdrawEarlyError {
+    #[msg("Deposit box has matured, use the regular withdraw")]
+    AlreadyMatured,
+    #[msg("Interest owed does not fit in a token amount")]
+    InterestOverflow,
+    #[msg("Vault liabilities are smaller than this deposit box")]
+    LiabilitiesUnderflow,
+    #[msg("Withdrawals are paused")]
+    WithdrawalsPaused,
+}
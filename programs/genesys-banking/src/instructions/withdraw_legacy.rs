@@ -0,0 +1,377 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{TokenAccount, Mint, Token}
+};
+use spl_token;
+use solana_program;
+
+use crate::instructions::initialize::VaultInfo;
+use crate::instructions::deposit::DepositInfo;
+use crate::events::LegacyWithdrawn;
+use crate::constants::*;
+use crate::math::{compound_interest, tenthbps_to_ray, Compounding};
+
+
+pub fn handler(
+    ctx: Context<WithdrawLegacy>,
+    legacy_info_bump: u8,
+) -> Result<()> {
+
+    // Both accounts are read in the layout the original program wrote them with
+    let legacy_vault_info = LegacyVaultInfo::load(&ctx.accounts.legacy_vault_info, ctx.program_id)?;
+    let deposit_info = LegacyDepositInfo::load(&ctx.accounts.deposit_info, ctx.program_id)?;
+    require!(
+        ctx.accounts.token_vault.key() == legacy_vault_info.token_vault
+            && ctx.accounts.token_mint.key() == legacy_vault_info.token_mint,
+        LegacyError::WrongLegacyVault
+    );
+
+    // The original withdraw left the box open, with its user vault emptied. Such a box
+    // was paid already and is only closed; otherwise it is paid at maturity.
+    let withdrawn = ctx.accounts.user_vault.amount < deposit_info.deposit_lamports;
+    require!(
+        withdrawn || deposit_info.after_lockout(Clock::get()?.unix_timestamp),
+        LegacyError::TooSoon
+    );
+    let interest = if withdrawn { Some(0) } else { deposit_info.interest(legacy_vault_info.interest_rate) };
+    require!(interest.is_some(), LegacyError::InterestOverflow);
+    let interest = interest.unwrap();
+    require!(ctx.accounts.token_vault.amount >= interest, LegacyError::NotEnoughTokensInReserve);
+
+    // The original vault_info PDA was seeded without a mint
+    let signer_seeds: &[&[u8]] = &[VAULT_INFO_SEED.as_bytes(), &[legacy_info_bump]];
+
+    // First, put the interest in the user vault
+    if interest > 0 {
+        let ix = spl_token::instruction::transfer_checked(
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.token_vault.key(),
+            &ctx.accounts.token_mint.key(),
+            &ctx.accounts.user_vault.key(),
+            &ctx.accounts.legacy_vault_info.key(),
+            &[&ctx.accounts.legacy_vault_info.key()],
+            interest,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Invoke using solana_program library
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.token_vault.to_account_info(),
+                ctx.accounts.legacy_vault_info.to_account_info(),
+                ctx.accounts.token_mint.to_account_info(),
+                ctx.accounts.user_vault.to_account_info(),
+            ],
+            &[signer_seeds],
+        )?;
+    }
+
+    // Second, return all tokens in the user vault to the user
+    let payout = ctx.accounts.user_vault.amount + interest;
+    if payout > 0 {
+        let ix = spl_token::instruction::transfer_checked(
+            &ctx.accounts.token_program.key(),
+            &ctx.accounts.user_vault.key(),
+            &ctx.accounts.token_mint.key(),
+            &ctx.accounts.depositor_token_account.key(),
+            &ctx.accounts.legacy_vault_info.key(),
+            &[&ctx.accounts.legacy_vault_info.key()],
+            payout,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        // Invoke using solana_program library
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.user_vault.to_account_info(),
+                ctx.accounts.legacy_vault_info.to_account_info(),
+                ctx.accounts.token_mint.to_account_info(),
+                ctx.accounts.depositor_token_account.to_account_info(),
+            ],
+            &[signer_seeds],
+        )?;
+    }
+
+    // Third, close the (now empty) user vault and return its rent to the user
+    let ix = spl_token::instruction::close_account(
+        &ctx.accounts.token_program.key(),
+        &ctx.accounts.user_vault.key(),
+        &ctx.accounts.depositor.key(),
+        &ctx.accounts.legacy_vault_info.key(),
+        &[&ctx.accounts.legacy_vault_info.key()],
+    )?;
+
+    // Invoke using solana_program library
+    solana_program::program::invoke_signed(
+        &ix,
+        &[
+            ctx.accounts.user_vault.to_account_info(),
+            ctx.accounts.depositor.to_account_info(),
+            ctx.accounts.legacy_vault_info.to_account_info(),
+        ],
+        &[signer_seeds],
+    )?;
+
+    // Finally, close the deposit info and return its rent to the user
+    let deposit_info_account = ctx.accounts.deposit_info.to_account_info();
+    let depositor = ctx.accounts.depositor.to_account_info();
+    **depositor.lamports.borrow_mut() = depositor.lamports().checked_add(deposit_info_account.lamports()).unwrap();
+    **deposit_info_account.lamports.borrow_mut() = 0;
+    deposit_info_account.data.borrow_mut().fill(0);
+
+    emit!(LegacyWithdrawn {
+        legacy_vault_info: ctx.accounts.legacy_vault_info.key(),
+        depositor: ctx.accounts.depositor.key(),
+        deposit_info: deposit_info_account.key(),
+        deposit_lamports: deposit_info.deposit_lamports,
+        interest,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+
+
+#[derive(Accounts)]
+#[instruction(
+    legacy_info_bump: u8,
+)]
+/// This WithdrawLegacy context is used to pay out a deposit box of the original singleton vault,
+/// created before vaults were keyed by mint and boxes were indexed.
+///
+/// Requirements
+/// ----------------------
+/// 1) The box and its vault are read in their original layout (see `LegacyDepositInfo`, `LegacyVaultInfo`).
+/// 2) The original vault_info PDA, seeded without a mint, signs for its reserve and the user vault.
+/// 3) Principal and the interest owed at maturity go to the depositor, and both box accounts are closed.
+pub struct WithdrawLegacy<'info> {
+
+    /// This account holds the metadata of the original vault
+    /// CHECK: This is fine because it is a PDA of this program, decoded by `LegacyVaultInfo::load`
+    #[account(
+        seeds = [VAULT_INFO_SEED.as_bytes()],
+        bump = legacy_info_bump,
+    )]
+    pub legacy_vault_info: UncheckedAccount<'info>,
+
+    /// This account holds the metadata for the deposit box being withdrawn
+    /// (closed on withdraw, rent goes back to the depositor)
+    /// CHECK: This is fine because it is a PDA of this program, decoded by `LegacyDepositInfo::load`
+    #[account(
+        mut,
+        seeds = [
+            USER_DEPOSIT_INFO.as_bytes(),
+            &depositor.key.to_bytes(),
+        ],
+        bump,
+    )]
+    pub deposit_info: UncheckedAccount<'info>,
+
+    /// This token account holds the principal of the box
+    /// (closed on withdraw, rent goes back to the depositor)
+    #[account(
+        mut,
+        seeds = [
+            USER_VAULT_SEED.as_bytes(),
+            &depositor.key.to_bytes(),
+        ],
+        bump,
+    )]
+    pub user_vault: Box<Account<'info, TokenAccount>>,
+
+    /// This token account is the reserve of the original vault
+    #[account(
+        mut,
+        seeds = [TOKEN_VAULT_SEED.as_bytes()],
+        bump,
+    )]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    /// This mint account holds the mint info of the SPL token
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    /// This account is the user/depositor
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// This account is the user's SPL token account
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        associated_token::mint = token_mint,
+        associated_token::authority = depositor,
+    )]
+    pub depositor_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// System Program
+    pub system_program: Program<'info, System>,
+
+    /// Token Program
+    pub token_program: Program<'info, Token>,
+
+    /// Token Program
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// Rent Program
+    pub rent: Sysvar<'info, Rent>,
+}
+
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+/// The vault_info of the original singleton vault, as it was laid out before vaults were keyed by mint
+pub struct LegacyVaultInfo {
+
+    /// Maximum number of tokens in vault
+    pub max_tokens: u64,
+
+    /// Interest rate (in tenths of bps per second)
+    pub interest_rate: u64,
+
+    /// The vault admin with mint+deposit authority
+    pub vault_admin: Pubkey,
+
+    /// The mint of the SPL token stored in the vault
+    pub token_mint: Pubkey,
+
+    /// The address of the vault holding the reserve
+    pub token_vault: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+/// A deposit box of the original singleton vault, as it was laid out before boxes were indexed
+pub struct LegacyDepositInfo {
+
+    /// Time in seconds deposited
+    pub seconds_locked: u64,
+
+    /// Deposited amount
+    pub deposit_lamports: u64,
+
+    /// The depositor
+    pub depositor: Pubkey,
+
+    /// Time deposited
+    pub deposit_time: i64,
+}
+
+impl LegacyVaultInfo {
+
+    /// Size of the account, discriminator included
+    pub const SPACE: usize = 8 + 8 + 8 + 32 * 3;
+
+    /// Decodes the original vault_info, which kept the `VaultInfo` discriminator
+    pub fn load(account: &AccountInfo, program_id: &Pubkey) -> Result<Self> {
+        load_legacy(account, program_id, &VaultInfo::discriminator(), Self::SPACE)
+    }
+}
+
+impl LegacyDepositInfo {
+
+    /// Size of the account, discriminator included
+    pub const SPACE: usize = 8 + 8 + 8 + 32 + 8;
+
+    /// Decodes an original deposit box, which kept the `DepositInfo` discriminator
+    pub fn load(account: &AccountInfo, program_id: &Pubkey) -> Result<Self> {
+        load_legacy(account, program_id, &DepositInfo::discriminator(), Self::SPACE)
+    }
+
+    pub fn after_lockout(&self, now: i64) -> bool {
+        now.saturating_sub(self.deposit_time) as u64 >= self.seconds_locked
+    }
+
+    /// Interest owed at maturity, compounded every second at the original vault's rate (rounded down)
+    pub fn interest(&self, interest_rate_tenthbps: u64) -> Option<u64> {
+        compound_interest(
+            self.deposit_lamports,
+            tenthbps_to_ray(interest_rate_tenthbps),
+            self.seconds_locked,
+            Compounding::PerSecond,
+        )
+    }
+}
+
+/// Decodes an account written by the original program, checking its owner, discriminator and size.
+fn load_legacy<T: AnchorDeserialize>(
+    account: &AccountInfo,
+    program_id: &Pubkey,
+    discriminator: &[u8; 8],
+    space: usize,
+) -> Result<T> {
+    require!(account.owner == program_id, LegacyError::NotLegacyAccount);
+    let data = account.try_borrow_data()?;
+    require!(data.len() == space && data[..8] == discriminator[..], LegacyError::NotLegacyAccount);
+    T::deserialize(&mut &data[8..]).map_err(|_| error!(LegacyError::NotLegacyAccount))
+}
+
+
+#[error_code]
+pub enum LegacyError {
+    #[msg("Account is not in the layout of the original vault")]
+    NotLegacyAccount,
+    #[msg("Reserve or mint does not belong to the original vault")]
+    WrongLegacyVault,
+    #[msg("User is trying to withdraw too soon")]
+    TooSoon,
+    #[msg("The reserve does not have enough tokens to pay you right now")]
+    NotEnoughTokensInReserve,
+    #[msg("Interest owed does not fit in a token amount")]
+    InterestOverflow,
+}
+
+#[test]
+fn test_legacy_layouts_decode(){
+    let program_id = Pubkey::new_unique();
+    let deposit_info = LegacyDepositInfo {
+        seconds_locked: 100,
+        deposit_lamports: 1_000,
+        depositor: Pubkey::new_unique(),
+        deposit_time: 1_000,
+    };
+    let mut data = DepositInfo::discriminator().to_vec();
+    deposit_info.serialize(&mut data).unwrap();
+    assert_eq!(data.len(), LegacyDepositInfo::SPACE);
+
+    let (key, mut lamports) = (Pubkey::new_unique(), 0);
+    let load = |data: &mut [u8], owner: &Pubkey, lamports: &mut u64| {
+        let account = AccountInfo::new(&key, false, false, lamports, data, owner, false, 0);
+        LegacyDepositInfo::load(&account, &program_id)
+    };
+    assert_eq!(load(&mut data.clone(), &program_id, &mut lamports).unwrap(), deposit_info);
+
+    // Accounts of another owner, type or layout are rejected
+    assert!(load(&mut data.clone(), &Pubkey::new_unique(), &mut lamports).is_err());
+    let mut vault_info = data.clone();
+    vault_info[..8].copy_from_slice(&VaultInfo::discriminator());
+    assert!(load(&mut vault_info, &program_id, &mut lamports).is_err());
+    let mut current = data.clone();
+    current.resize(8 + std::mem::size_of::<DepositInfo>(), 0);
+    assert!(load(&mut current, &program_id, &mut lamports).is_err());
+
+    // The original vault kept the `VaultInfo` discriminator
+    let legacy_vault_info = LegacyVaultInfo { interest_rate: 35, ..Default::default() };
+    let mut data = VaultInfo::discriminator().to_vec();
+    legacy_vault_info.serialize(&mut data).unwrap();
+    let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &program_id, false, 0);
+    assert_eq!(LegacyVaultInfo::load(&account, &program_id).unwrap(), legacy_vault_info);
+}
+
+#[test]
+fn test_legacy_box_is_paid_at_maturity(){
+    let deposit_info = LegacyDepositInfo {
+        seconds_locked: 100,
+        deposit_lamports: 1_000,
+        deposit_time: 1_000,
+        ..Default::default()
+    };
+    assert!(!deposit_info.after_lockout(1_099));
+    assert!(deposit_info.after_lockout(1_100));
+
+    // 1,000 tokens for 100 seconds at 35 tenths of a bps a second: 1000 * (1.00035^100 - 1) = 35.6
+    assert_eq!(deposit_info.interest(35), Some(35));
+    assert_eq!(deposit_info.interest(0), Some(0));
+}
@@ -4,14 +4,34 @@ declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
 pub mod instructions;
 pub mod constants;
+pub mod math;
+pub mod events;
+#[cfg(test)]
+mod fixtures;
 
 use instructions::{
     initialize::*,
     deposit::*,
     withdraw::*,
-    refresh_reserve::*
+    refresh_reserve::*,
+    top_up::*,
+    update_vault_config::*,
+    withdraw_early::*,
+    fund_reserve::*,
+    sweep_reserve::*,
+    propose_admin::*,
+    accept_admin::*,
+    cancel_admin_transfer::*,
+    set_role::*,
+    set_pause::*,
+    emergency_withdraw::*,
+    set_rate_tiers::*,
+    set_product::*,
+    deposit_product::*,
+    set_compounding::*,
+    withdraw_legacy::*
 };
-use crate::constants::*;
+use math::Compounding;
 
 #[program]
 pub mod genesys_banking {
@@ -19,9 +39,11 @@ pub mod genesys_banking {
     use super::*;
 
     pub fn initialize(
-        ctx: Context<InitializeVault>
+        ctx: Context<InitializeVault>,
+        config: VaultConfig,
+        external_mint: bool,
     ) -> Result<()> {
-        instructions::initialize::handler(ctx)
+        instructions::initialize::handler(ctx, config, external_mint)
     }
 
     pub fn deposit(
@@ -44,16 +66,166 @@ pub mod genesys_banking {
         vault_info_bump: u8,
         _deposit_info_bump: u8,
         _user_vault_bump: u8,
+        _deposit_index: u64,
     ) -> Result<()> {
         instructions::withdraw::handler(ctx, vault_info_bump)
     }
 
-    pub fn refresh_reserve(
-        ctx: Context<RefreshReserve>,
+    pub fn top_up(
+        ctx: Context<TopUp>,
+        _reserve_bump: u8,
+        vault_info_bump: u8,
+        _deposit_info_bump: u8,
+        _user_vault_bump: u8,
+        _deposit_index: u64,
+        top_up_lamports: u64,
+    ) -> Result<()> {
+        instructions::top_up::handler(ctx, vault_info_bump, top_up_lamports)
+    }
+
+    pub fn refresh_reserve<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefreshReserve<'info>>,
+        info_bump: u8,
+        _reserve_bump: u8,
+    ) -> Result<()> {
+        instructions::refresh_reserve::handler(ctx, info_bump)
+    }
+
+    pub fn update_vault_config(
+        ctx: Context<UpdateVaultConfig>,
+        _info_bump: u8,
+        config: VaultConfig,
+    ) -> Result<()> {
+        instructions::update_vault_config::handler(ctx, config)
+    }
+
+    pub fn withdraw_early(
+        ctx: Context<WithdrawEarly>,
+        _reserve_bump: u8,
+        vault_info_bump: u8,
+        _deposit_info_bump: u8,
+        _user_vault_bump: u8,
+        _deposit_index: u64,
+    ) -> Result<()> {
+        instructions::withdraw_early::handler(ctx, vault_info_bump)
+    }
+
+    pub fn fund_reserve<'info>(
+        ctx: Context<'_, '_, '_, 'info, FundReserve<'info>>,
+        info_bump: u8,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::fund_reserve::handler(ctx, info_bump, amount)
+    }
+
+    pub fn sweep_reserve(
+        ctx: Context<SweepReserve>,
+        info_bump: u8,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::sweep_reserve::handler(ctx, info_bump, amount)
+    }
+
+    pub fn propose_admin(
+        ctx: Context<ProposeAdmin>,
         _info_bump: u8,
-        reserve_bump: u8,
+        new_admin: Pubkey,
+    ) -> Result<()> {
+        instructions::propose_admin::handler(ctx, new_admin)
+    }
+
+    pub fn accept_admin(
+        ctx: Context<AcceptAdmin>,
+        info_bump: u8,
+    ) -> Result<()> {
+        instructions::accept_admin::handler(ctx, info_bump)
+    }
+
+    pub fn cancel_admin_transfer(
+        ctx: Context<CancelAdminTransfer>,
+        info_bump: u8,
+    ) -> Result<()> {
+        instructions::cancel_admin_transfer::handler(ctx, info_bump)
+    }
+
+    pub fn set_role(
+        ctx: Context<SetRole>,
+        _info_bump: u8,
+        role: Role,
+        account: Pubkey,
+    ) -> Result<()> {
+        instructions::set_role::handler(ctx, role, account)
+    }
+
+    pub fn set_pause(
+        ctx: Context<SetPause>,
+        _info_bump: u8,
+        deposits_paused: bool,
+        refreshes_paused: bool,
+        withdrawals_paused: bool,
+        emergency_mode: bool,
+    ) -> Result<()> {
+        instructions::set_pause::handler(
+            ctx,
+            deposits_paused,
+            refreshes_paused,
+            withdrawals_paused,
+            emergency_mode,
+        )
+    }
+
+    pub fn emergency_withdraw(
+        ctx: Context<EmergencyWithdraw>,
+        vault_info_bump: u8,
+        _deposit_info_bump: u8,
+        _user_vault_bump: u8,
+        _deposit_index: u64,
+    ) -> Result<()> {
+        instructions::emergency_withdraw::handler(ctx, vault_info_bump)
+    }
+
+    pub fn set_rate_tiers(
+        ctx: Context<SetRateTiers>,
+        _info_bump: u8,
+        tiers: Vec<RateTier>,
+    ) -> Result<()> {
+        instructions::set_rate_tiers::handler(ctx, tiers)
+    }
+
+    pub fn set_product(
+        ctx: Context<SetProduct>,
+        _info_bump: u8,
+        product_id: u64,
+        terms: ProductTerms,
+    ) -> Result<()> {
+        instructions::set_product::handler(ctx, product_id, terms)
+    }
+
+    pub fn deposit_product(
+        ctx: Context<DepositProduct>,
+        _reserve_bump: u8,
+        _vault_info_bump: u8,
+        _deposit_info_bump: u8,
+        _user_vault_bump: u8,
+        _product_id: u64,
+        deposit_lamports: u64,
+    ) -> Result<()> {
+        instructions::deposit_product::handler(ctx, deposit_lamports)
+    }
+
+    pub fn set_compounding(
+        ctx: Context<SetCompounding>,
+        _info_bump: u8,
+        compounding: Compounding,
+    ) -> Result<()> {
+        instructions::set_compounding::handler(ctx, compounding)
+    }
+
+    pub fn withdraw_legacy(
+        ctx: Context<WithdrawLegacy>,
+        legacy_info_bump: u8,
     ) -> Result<()> {
-        instructions::refresh_reserve::handler(ctx, reserve_bump)
+        instructions::withdraw_legacy::handler(ctx, legacy_info_bump)
     }
 }
 
@@ -0,0 +1,759 @@
+//! Deterministic fixed-point math used for interest computations.
+//!
+//! All values are unsigned integers scaled by `RAY` (1e27), so every validator and
+//! off-chain client computes bit-identical results. The extra precision over the usual 1e18
+//! keeps payouts exact to the base unit even for deposits of 9 decimal mints.
+//!
+//! Rounding rules
+//! ----------------------
+//! 1) Payouts always round down (against the depositor, never against the bank).
+//! 2) Solvency limits (e.g. the maximum lock time) are computed with factors rounded up,
+//!    so the bank never promises more than its reserve could cover.
+
+use anchor_lang::prelude::*;
+use std::convert::TryFrom;
+
+/// Fixed-point scale (1e27)
+pub const RAY: u128 = 1_000_000_000_000_000_000_000_000_000;
+
+/// One tenth of a basis point expressed in `RAY` (1e-5)
+pub const TENTHBPS_RAY: u128 = RAY / 100_000;
+
+/// One basis point expressed in `RAY` (1e-4)
+pub const BPS_RAY: u128 = RAY / 10_000;
+
+/// Seconds in a day, the period of `Compounding::Daily`
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Seconds in a (365 day) year, the period annual rates are quoted over
+pub const SECONDS_PER_YEAR: u64 = 365 * SECONDS_PER_DAY;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Direction in which a fixed-point operation rounds
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Convention used to compound a per-second rate
+pub enum Compounding {
+    /// Interest compounds every second: `(1 + rate)^seconds`
+    #[default]
+    PerSecond,
+
+    /// Interest never compounds: `1 + rate * seconds`
+    Simple,
+
+    /// Interest compounds once a day, and accrues simply within the last day:
+    /// `(1 + rate * 86400)^days * (1 + rate * remaining_seconds)`
+    Daily,
+
+    /// Interest compounds continuously: `e^(rate * seconds)`
+    Continuous,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// How an annual rate is quoted
+pub enum RateBasis {
+    /// Nominal annual rate: the per-second rate times the seconds in a year
+    #[default]
+    Apr,
+
+    /// Effective annual yield: what one unit earns over a year under the compounding convention
+    Apy,
+}
+
+impl Compounding {
+
+    /// Growth factor of one unit over `seconds` at `rate_ray` under this convention, in `RAY`
+    pub fn factor(self, rate_ray: u128, seconds: u64, rounding: Rounding) -> Option<u128> {
+        match self {
+            Compounding::PerSecond => compound_factor(rate_ray, seconds, rounding),
+            Compounding::Simple => RAY.checked_add(rate_ray.checked_mul(seconds as u128)?),
+            Compounding::Daily => {
+                let days = seconds / SECONDS_PER_DAY;
+                let remaining_seconds = seconds % SECONDS_PER_DAY;
+                let daily_rate = rate_ray.checked_mul(SECONDS_PER_DAY as u128)?;
+                let last_day = RAY.checked_add(rate_ray.checked_mul(remaining_seconds as u128)?)?;
+                ray_mul(compound_factor(daily_rate, days, rounding)?, last_day, rounding)
+            }
+            Compounding::Continuous => exp_ray(rate_ray.checked_mul(seconds as u128)?, rounding),
+        }
+    }
+}
+
+/// Converts a rate given in tenths of bps into a `RAY` scaled rate
+pub fn tenthbps_to_ray(rate_tenthbps: u64) -> u128 {
+    rate_tenthbps as u128 * TENTHBPS_RAY
+}
+
+/// Most unit steps `apy_to_per_second_ray` takes from its closed-form estimate
+pub const MAX_APY_CORRECTIONS: u32 = 8;
+
+/// `ln(2)` in `RAY`
+const LN_2_RAY: u128 = 693_147_180_559_945_309_417_232_121;
+
+/// Converts an annual rate given in bps into the per-second `RAY` rate it is compounded with.
+///
+/// An APR is divided by the seconds in a year. For an APY, the result is the largest per-second
+/// rate whose yearly growth under `compounding` (rounded up) stays within the quoted yield, so the
+/// effective yield never exceeds the quote (see `apy_to_per_second_ray`). Both round down.
+pub fn annual_bps_to_per_second_ray(annual_bps: u64, basis: RateBasis, compounding: Compounding) -> Option<u128> {
+    match basis {
+        RateBasis::Apr => Some((annual_bps as u128).checked_mul(BPS_RAY)? / SECONDS_PER_YEAR as u128),
+        RateBasis::Apy => apy_to_per_second_ray(annual_bps, compounding).map(|(rate, _)| rate),
+    }
+}
+
+/// Converts an APY in bps into a per-second rate under `compounding`, returning the rate and
+/// the number of unit corrections made to its estimate.
+///
+/// The rate is estimated in closed form from `ln(1 + apy)`: spread over the year for continuous
+/// compounding, and turned back into a per-period rate with `e^x - 1` for per-second and daily
+/// compounding. The estimate is within a few units of the answer, which is then reached by
+/// stepping one unit at a time, checking each candidate with a single factor evaluation.
+/// Gives up (`None`) after `MAX_APY_CORRECTIONS` steps, so the cost is bounded.
+fn apy_to_per_second_ray(annual_bps: u64, compounding: Compounding) -> Option<(u128, u32)> {
+    let yearly_factor = RAY.checked_add((annual_bps as u128).checked_mul(BPS_RAY)?)?;
+    let fits = |rate: u128| {
+        compounding.factor(rate, SECONDS_PER_YEAR, Rounding::Up)
+            .map_or(false, |factor| factor <= yearly_factor)
+    };
+
+    let log_growth = ln_ray(yearly_factor)?;
+    let mut rate = match compounding {
+        Compounding::Simple => (yearly_factor - RAY) / SECONDS_PER_YEAR as u128,
+        Compounding::Continuous => log_growth / SECONDS_PER_YEAR as u128,
+        Compounding::PerSecond => exp_m1_ray(log_growth / SECONDS_PER_YEAR as u128)?,
+        Compounding::Daily => exp_m1_ray(log_growth / (SECONDS_PER_YEAR / SECONDS_PER_DAY) as u128)? / SECONDS_PER_DAY as u128,
+    };
+
+    for corrections in 0..=MAX_APY_CORRECTIONS {
+        if !fits(rate) {
+            rate = rate.checked_sub(1)?;
+        } else if fits(rate + 1) {
+            rate += 1;
+        } else {
+            return Some((rate, corrections));
+        }
+    }
+    None
+}
+
+/// Computes `ln(x)` in `RAY` for a `RAY` scaled `x >= 1`, rounded down to within a few units.
+///
+/// `x` is halved to `m` in `[1, 2)`, and `ln(m) = 2 * atanh((m - 1) / (m + 1))` is summed as a series.
+fn ln_ray(x: u128) -> Option<u128> {
+    if x < RAY {
+        return None;
+    }
+    let mut halvings = 0;
+    let mut m = x;
+    while m >= 2 * RAY {
+        m /= 2;
+        halvings += 1;
+    }
+
+    let z = ray_div(m - RAY, m + RAY)?;
+    let z2 = ray_mul(z, z, Rounding::Down)?;
+    let mut sum = 0;
+    let mut term = z;
+    let mut k = 1;
+    while term > 0 {
+        sum += term / k;
+        term = ray_mul(term, z2, Rounding::Down)?;
+        k += 2;
+    }
+    LN_2_RAY.checked_mul(halvings)?.checked_add(2 * sum)
+}
+
+/// Computes `e^x - 1` in `RAY` for a small `RAY` scaled `x`, summed as a Taylor series and rounded down.
+fn exp_m1_ray(x: u128) -> Option<u128> {
+    let mut sum = 0u128;
+    let mut term = x;
+    let mut k = 1;
+    while term > 0 {
+        sum = sum.checked_add(term)?;
+        k += 1;
+        term = ray_mul(term, x, Rounding::Down)? / k;
+    }
+    Some(sum)
+}
+
+/// Computes `a * RAY / b` rounded down, in three long-division steps of 1e9.
+/// Returns `None` if `b` is too large for the steps (above about 3e29) or the result does not fit.
+fn ray_div(a: u128, b: u128) -> Option<u128> {
+    const STEP: u128 = 1_000_000_000;
+    if b == 0 || b > u128::MAX / STEP {
+        return None;
+    }
+    let mut quotient = a / b;
+    let mut remainder = a % b;
+    for _ in 0..3 {
+        remainder *= STEP;
+        quotient = quotient.checked_mul(STEP)?.checked_add(remainder / b)?;
+        remainder %= b;
+    }
+    Some(quotient)
+}
+
+/// Converts an amount of whole tokens into base units of a mint with `decimals` decimals.
+/// Returns `None` if the result does not fit in a u64.
+pub fn to_base_units(amount: u64, decimals: u8) -> Option<u64> {
+    amount.checked_mul(10u64.checked_pow(decimals as u32)?)
+}
+
+/// Computes `a * b / RAY` with the given rounding, without any intermediate overflow.
+///
+/// Splitting `a = ah * RAY + al` and `b = bh * RAY + bl` gives
+/// `a * b / RAY = ah * bh * RAY + ah * bl + al * bh + al * bl / RAY`,
+/// where only the last term is fractional (see `ray_mul_fraction`).
+/// Returns `None` only if the result itself does not fit in a u128.
+pub fn ray_mul(a: u128, b: u128, rounding: Rounding) -> Option<u128> {
+    let (ah, al) = (a / RAY, a % RAY);
+    let (bh, bl) = (b / RAY, b % RAY);
+
+    let (low, inexact) = ray_mul_fraction(al, bl);
+    let mut result = ah
+        .checked_mul(bh)?
+        .checked_mul(RAY)?
+        .checked_add(ah.checked_mul(bl)?)?
+        .checked_add(al.checked_mul(bh)?)?
+        .checked_add(low)?;
+    if rounding == Rounding::Up && inexact {
+        result = result.checked_add(1)?;
+    }
+    Some(result)
+}
+
+/// Computes `a * b / RAY` rounded down for `a, b < RAY`, and whether it was inexact.
+///
+/// `a * b` can reach 1e54, so both operands are split into 1e14 limbs
+/// (`a = a1 * 1e14 + a0`), which keeps every partial product below 1e28:
+/// `a * b / RAY = a1 * b1 * 10 + (a1 * b0 + a0 * b1) / 1e13 + a0 * b0 / RAY`.
+fn ray_mul_fraction(a: u128, b: u128) -> (u128, bool) {
+    const LIMB: u128 = 100_000_000_000_000;
+    const MID_SCALE: u128 = RAY / LIMB;
+
+    let (a1, a0) = (a / LIMB, a % LIMB);
+    let (b1, b0) = (b / LIMB, b % LIMB);
+
+    let mid = a1 * b0 + a0 * b1;
+    let low = (mid % MID_SCALE) * LIMB + a0 * b0;
+    (a1 * b1 * 10 + mid / MID_SCALE + low / RAY, low % RAY != 0)
+}
+
+/// Computes the per-second compounding factor `(1 + rate)^seconds` in `RAY`,
+/// using exponentiation by squaring and rounding every step in the given direction.
+pub fn compound_factor(rate_ray: u128, seconds: u64, rounding: Rounding) -> Option<u128> {
+    let mut factor = RAY;
+    let mut base = RAY.checked_add(rate_ray)?;
+    let mut exponent = seconds;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            factor = ray_mul(factor, base, rounding)?;
+        }
+        exponent >>= 1;
+        // Only square when it is still needed, since base^(2^k) <= factor for 2^k <= seconds
+        if exponent > 0 {
+            base = ray_mul(base, base, rounding)?;
+        }
+    }
+    Some(factor)
+}
+
+/// Computes `e^x` in `RAY` for a `RAY` scaled `x`, rounding every step in the given direction.
+///
+/// `x` is halved until it is at most 1/2, `e^(x / 2^n)` is summed as a Taylor series, and the
+/// result is squared back `n` times. Rounding up adds a bound on the truncated tail of the series
+/// (each term is at most half the previous one). Returns `None` if the result does not fit.
+pub fn exp_ray(x: u128, rounding: Rounding) -> Option<u128> {
+    let mut halvings = 0;
+    while x >> halvings > RAY / 2 {
+        halvings += 1;
+    }
+    let mut y = x >> halvings;
+    if rounding == Rounding::Up && y << halvings != x {
+        y += 1;
+    }
+
+    let mut sum = RAY;
+    let mut term = RAY;
+    let mut k = 1;
+    loop {
+        term = ray_mul(term, y, rounding)?;
+        term = match rounding {
+            Rounding::Down => term / k,
+            Rounding::Up => (term + k - 1) / k,
+        };
+        if term == 0 || (rounding == Rounding::Up && term == 1) {
+            break;
+        }
+        sum = sum.checked_add(term)?;
+        k += 1;
+    }
+    if rounding == Rounding::Up {
+        sum = sum.checked_add(2 * term)?;
+    }
+
+    for _ in 0..halvings {
+        sum = ray_mul(sum, sum, rounding)?;
+    }
+    Some(sum)
+}
+
+/// Interest earned by `principal` at `rate_ray` for `seconds` under `compounding`,
+/// rounded down. Returns `None` if the interest does not fit in a u64.
+pub fn compound_interest(
+    principal: u64,
+    rate_ray: u128,
+    seconds: u64,
+    compounding: Compounding,
+) -> Option<u64> {
+    let factor = compounding.factor(rate_ray, seconds, Rounding::Down)?;
+    let interest = ray_mul(principal as u128, factor - RAY, Rounding::Down)?;
+    u64::try_from(interest).ok()
+}
+
+/// Whether `principal` growing at `rate_ray` under `compounding` for `seconds` stays within
+/// `max_balance` (e.g. `principal * (1 + rate)^seconds <= max_balance`).
+///
+/// The factor is rounded up, so a duration that fits surely fits. This evaluates a single factor
+/// (a handful of multiplications), so it is the check to run on chain.
+pub fn compounded_balance_fits(
+    principal: u64,
+    rate_ray: u128,
+    seconds: u64,
+    max_balance: u64,
+    compounding: Compounding,
+) -> bool {
+    compounding.factor(rate_ray, seconds, Rounding::Up)
+        .and_then(|factor| ray_mul(principal as u128, factor, Rounding::Up))
+        .map_or(false, |balance| balance <= max_balance as u128)
+}
+
+/// Largest number of seconds for which `principal` growing at `rate_ray` under `compounding`
+/// stays within `max_balance`, i.e. the last duration `compounded_balance_fits` accepts.
+///
+/// Growth beyond what a `RAY` factor can hold (about 3.4e11x) is treated as not fitting.
+/// The growth factor must be nondecreasing in time, which holds for every convention.
+/// This searches over durations, so it is meant for tooling and tests rather than on chain use.
+pub fn max_compounding_seconds(
+    principal: u64,
+    rate_ray: u128,
+    max_balance: u64,
+    compounding: Compounding,
+) -> u64 {
+    let fits = |seconds: u64| compounded_balance_fits(principal, rate_ray, seconds, max_balance, compounding);
+
+    if principal == 0 {
+        return u64::MAX;
+    }
+    if !fits(0) {
+        return 0;
+    }
+
+    // Find a duration that no longer fits by doubling
+    let mut lo = 0;
+    let mut hi = 1;
+    while fits(hi) {
+        if hi == u64::MAX {
+            return u64::MAX;
+        }
+        lo = hi;
+        hi = hi.saturating_mul(2);
+    }
+
+    // Binary search with fits(lo) && !fits(hi)
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if fits(mid) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+    use crate::constants::MAX_ANNUAL_RATE_BPS;
+
+    // Parameters of the reference vault
+    const MAX_RESERVE_TOKEN_AMOUNT: u64 = 10_000_000;
+    const MAX_USER_DEPOSIT: u64 = 100_000;
+    const INTEREST_RATE_TENTHBPS: u64 = 35;
+
+    /// Scale of the high-precision reference (1e60)
+    fn reference_scale() -> BigUint {
+        BigUint::from(10u32).pow(60)
+    }
+
+    /// Reference factors `(1 + rate)^n` for every n in 0..=max_seconds, scaled by 1e60,
+    /// returned as (rounded down, rounded up) bounds of the exact value.
+    fn reference_factors(rate_tenthbps: u64, max_seconds: u64) -> Vec<(BigUint, BigUint)> {
+        let numerator = BigUint::from(100_000 + rate_tenthbps);
+        let denominator = BigUint::from(100_000u64);
+        let mut down = reference_scale();
+        let mut up = reference_scale();
+        let mut factors = Vec::with_capacity(max_seconds as usize + 1);
+        factors.push((down.clone(), up.clone()));
+        for _ in 0..max_seconds {
+            down = &down * &numerator / &denominator;
+            up = (&up * &numerator + &denominator - 1u32) / &denominator;
+            factors.push((down.clone(), up.clone()));
+        }
+        factors
+    }
+
+    /// floor(principal * (factor - 1)) using a reference factor
+    fn reference_interest(principal: u64, factor: &BigUint) -> BigUint {
+        BigUint::from(principal) * (factor - reference_scale()) / reference_scale()
+    }
+
+    fn assert_interest_matches_reference(
+        principal: u64,
+        seconds: u64,
+        (down, up): &(BigUint, BigUint),
+    ) {
+        let interest = compound_interest(
+            principal,
+            tenthbps_to_ray(INTEREST_RATE_TENTHBPS),
+            seconds,
+            Compounding::PerSecond,
+        ).unwrap();
+        let interest = BigUint::from(interest);
+
+        // Never above the exact interest, and at most one unit below it
+        assert!(interest <= reference_interest(principal, up),
+            "principal {} seconds {} rounds against the bank", principal, seconds);
+        assert!(interest + 1u32 >= reference_interest(principal, down),
+            "principal {} seconds {} is more than one unit short", principal, seconds);
+    }
+
+    #[test]
+    fn test_ray_mul_is_exact() {
+        assert_eq!(ray_mul(3 * RAY, 5 * RAY, Rounding::Down), Some(15 * RAY));
+        assert_eq!(ray_mul(1, 1, Rounding::Down), Some(0));
+        assert_eq!(ray_mul(1, 1, Rounding::Up), Some(1));
+        assert_eq!(ray_mul(RAY / 2, 3, Rounding::Down), Some(1));
+        assert_eq!(ray_mul(RAY / 2, 3, Rounding::Up), Some(2));
+
+        // Large operands don't overflow as long as the result fits
+        let big = u128::MAX / RAY;
+        assert_eq!(ray_mul(big, RAY, Rounding::Down), Some(big));
+        assert_eq!(ray_mul(u128::MAX, 2 * RAY, Rounding::Down), None);
+
+        // Fractional parts are multiplied exactly, even when their product exceeds a u128
+        for (a, b) in [(RAY - 1, RAY - 1), (RAY / 3, RAY - 7), (123_456_789_012_345_678_901_234_567, 987_654_321_098_765_432_109_876_543)] {
+            let exact = BigUint::from(a) * BigUint::from(b);
+            let ray = BigUint::from(RAY);
+            assert_eq!(BigUint::from(ray_mul(a, b, Rounding::Down).unwrap()), &exact / &ray);
+            assert_eq!(BigUint::from(ray_mul(a, b, Rounding::Up).unwrap()), (&exact + &ray - 1u32) / &ray);
+        }
+    }
+
+    #[test]
+    fn test_compound_factor_bounds_exact_value() {
+        // (1 + 1e-5)^2 = 1.0000200001
+        let rate = tenthbps_to_ray(1);
+        let exact = RAY + 2 * TENTHBPS_RAY + TENTHBPS_RAY / 100_000;
+        assert_eq!(compound_factor(rate, 2, Rounding::Down), Some(exact));
+        assert_eq!(compound_factor(rate, 2, Rounding::Up), Some(exact));
+        assert_eq!(compound_factor(rate, 0, Rounding::Down), Some(RAY));
+        assert_eq!(compound_factor(0, u64::MAX, Rounding::Up), Some(RAY));
+        assert_eq!(compound_factor(RAY, 200, Rounding::Down), None);
+    }
+
+    #[test]
+    fn test_interest_over_full_duration_range() {
+        let rate = tenthbps_to_ray(INTEREST_RATE_TENTHBPS);
+        let longest = max_compounding_seconds(1, rate, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond);
+        let factors = reference_factors(INTEREST_RATE_TENTHBPS, longest);
+
+        // Every duration a deposit of these sizes could be locked for
+        for principal in [1, 2, 7, 100, 999, 12_345, 54_321, 99_999, MAX_USER_DEPOSIT] {
+            let max_seconds = max_compounding_seconds(principal, rate, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond);
+            for seconds in 0..=max_seconds {
+                assert_interest_matches_reference(principal, seconds, &factors[seconds as usize]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_interest_over_full_deposit_range() {
+        let rate = tenthbps_to_ray(INTEREST_RATE_TENTHBPS);
+        let longest = max_compounding_seconds(1, rate, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond);
+        let factors = reference_factors(INTEREST_RATE_TENTHBPS, longest);
+
+        // Every deposit size at a spread of durations, up to its longest possible lock
+        for principal in 1..=MAX_USER_DEPOSIT {
+            let max_seconds = max_compounding_seconds(principal, rate, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond);
+            let stride = (principal % 977) + 1;
+            for seconds in (0..=max_seconds).step_by(stride as usize * 401).chain([max_seconds]) {
+                assert_interest_matches_reference(principal, seconds, &factors[seconds as usize]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_seconds_matches_reference() {
+        let rate = tenthbps_to_ray(INTEREST_RATE_TENTHBPS);
+        let longest = max_compounding_seconds(1, rate, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond);
+        let factors = reference_factors(INTEREST_RATE_TENTHBPS, longest + 1);
+        let max_balance = BigUint::from(MAX_RESERVE_TOKEN_AMOUNT) * reference_scale();
+
+        for principal in (1..=MAX_USER_DEPOSIT).step_by(13).chain([MAX_USER_DEPOSIT]) {
+            let seconds = max_compounding_seconds(principal, rate, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond) as usize;
+
+            // The solved duration surely fits, and two more seconds surely don't
+            assert!(BigUint::from(principal) * &factors[seconds].1 <= max_balance);
+            if seconds + 2 < factors.len() {
+                assert!(BigUint::from(principal) * &factors[seconds + 2].0 > max_balance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_base_units() {
+        assert_eq!(to_base_units(100_000, 0), Some(100_000));
+        assert_eq!(to_base_units(100_000, 6), Some(100_000_000_000));
+        assert_eq!(to_base_units(10_000_000, 9), Some(10_000_000_000_000_000));
+        assert_eq!(to_base_units(1, 19), Some(10_000_000_000_000_000_000));
+        assert_eq!(to_base_units(2, 19), None);
+        assert_eq!(to_base_units(1, 20), None);
+    }
+
+    #[test]
+    fn test_interest_at_high_magnitudes() {
+        let rate = tenthbps_to_ray(INTEREST_RATE_TENTHBPS);
+        let longest = max_compounding_seconds(MAX_USER_DEPOSIT, rate, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond);
+        let factors = reference_factors(INTEREST_RATE_TENTHBPS, longest);
+
+        // Deposits of 6 and 9 decimal mints, up to half the u64 range
+        for principal in [
+            MAX_USER_DEPOSIT * 1_000_000,
+            MAX_USER_DEPOSIT * 1_000_000_000,
+            MAX_RESERVE_TOKEN_AMOUNT * 1_000_000_000,
+            u64::MAX / 2,
+        ] {
+            // Up to the longest lock whose balance still fits in a u64
+            let max_seconds = max_compounding_seconds(principal, rate, u64::MAX, Compounding::PerSecond).min(longest);
+            for seconds in [0, 1, 2, 59, 3_600, 10_000, max_seconds].iter().copied().filter(|&s| s <= max_seconds) {
+                assert_interest_matches_reference(principal, seconds, &factors[seconds as usize]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_seconds_is_scale_invariant() {
+        let rate = tenthbps_to_ray(INTEREST_RATE_TENTHBPS);
+        let unscaled = max_compounding_seconds(MAX_USER_DEPOSIT, rate, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond);
+
+        // The same vault expressed in base units of 6 and 9 decimal mints allows the same lock
+        for decimals in [6, 9] {
+            let scaled = max_compounding_seconds(
+                to_base_units(MAX_USER_DEPOSIT, decimals).unwrap(),
+                rate,
+                to_base_units(MAX_RESERVE_TOKEN_AMOUNT, decimals).unwrap(),
+                Compounding::PerSecond,
+            );
+            assert!(scaled.abs_diff(unscaled) <= 1, "decimals {}: {} vs {}", decimals, scaled, unscaled);
+        }
+    }
+
+    #[test]
+    fn test_max_seconds_edge_cases() {
+        let rate = tenthbps_to_ray(INTEREST_RATE_TENTHBPS);
+        assert_eq!(max_compounding_seconds(0, rate, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond), u64::MAX);
+        assert_eq!(max_compounding_seconds(1, 0, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond), u64::MAX);
+        assert_eq!(max_compounding_seconds(MAX_RESERVE_TOKEN_AMOUNT + 1, rate, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond), 0);
+        assert_eq!(max_compounding_seconds(MAX_RESERVE_TOKEN_AMOUNT, rate, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond), 0);
+    }
+
+    #[test]
+    fn test_balance_fits_agrees_with_max_seconds() {
+        let rate = tenthbps_to_ray(INTEREST_RATE_TENTHBPS);
+        let fits = |seconds| compounded_balance_fits(MAX_USER_DEPOSIT, rate, seconds, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond);
+        assert!(fits(13_159));
+        assert!(!fits(13_160));
+
+        // At 5% a year, interest reaches a hundred times the deposit after about 92 years
+        let rate = annual_bps_to_per_second_ray(500, RateBasis::Apr, Compounding::PerSecond).unwrap();
+        let fits = |years| compounded_balance_fits(MAX_USER_DEPOSIT, rate, years * SECONDS_PER_YEAR, MAX_RESERVE_TOKEN_AMOUNT, Compounding::PerSecond);
+        assert!(fits(92));
+        assert!(!fits(93));
+
+        for compounding in [Compounding::Simple, Compounding::Daily, Compounding::PerSecond, Compounding::Continuous] {
+            let seconds = max_compounding_seconds(MAX_USER_DEPOSIT, rate, MAX_RESERVE_TOKEN_AMOUNT, compounding);
+            assert!(compounded_balance_fits(MAX_USER_DEPOSIT, rate, seconds, MAX_RESERVE_TOKEN_AMOUNT, compounding));
+            assert!(!compounded_balance_fits(MAX_USER_DEPOSIT, rate, seconds + 1, MAX_RESERVE_TOKEN_AMOUNT, compounding));
+        }
+    }
+
+    /// Reference `e^(rate * seconds)`, scaled by 1e60, accurate to far below one unit of a payout
+    fn reference_exp(rate_tenthbps: u64, seconds: u64) -> BigUint {
+        let x = BigUint::from(rate_tenthbps) * BigUint::from(seconds) * BigUint::from(10u32).pow(55);
+        let mut sum = reference_scale();
+        let mut term = reference_scale();
+        let mut k = 1u32;
+        while term > BigUint::from(0u32) {
+            term = term * &x / reference_scale() / k;
+            sum += &term;
+            k += 1;
+        }
+        sum
+    }
+
+    #[test]
+    fn test_simple_and_daily_interest_are_exact() {
+        let rate = tenthbps_to_ray(INTEREST_RATE_TENTHBPS);
+
+        // 100,000 * 35e-5 * 100
+        assert_eq!(compound_interest(100_000, rate, 100, Compounding::Simple), Some(3_500));
+
+        // Within the first day, daily compounding accrues simply
+        for seconds in [0, 1, 100, SECONDS_PER_DAY - 1] {
+            assert_eq!(
+                compound_interest(1_000_000, tenthbps_to_ray(1), seconds, Compounding::Daily),
+                compound_interest(1_000_000, tenthbps_to_ray(1), seconds, Compounding::Simple),
+            );
+        }
+
+        // 1,000,000 * ((1 + 0.864)^2 * (1 + 0.00001 * 10) - 1)
+        assert_eq!(
+            compound_interest(1_000_000, tenthbps_to_ray(1), 2 * SECONDS_PER_DAY + 10, Compounding::Daily),
+            Some(2_474_843),
+        );
+    }
+
+    #[test]
+    fn test_continuous_interest_matches_reference() {
+        let rate = tenthbps_to_ray(INTEREST_RATE_TENTHBPS);
+        for principal in [1, 999, MAX_USER_DEPOSIT, MAX_USER_DEPOSIT * 1_000_000_000] {
+            let max_seconds = max_compounding_seconds(principal, rate, MAX_RESERVE_TOKEN_AMOUNT * 1_000_000_000, Compounding::Continuous);
+            for seconds in [0, 1, 2, 59, 1_000, 3_600, 10_000, max_seconds].iter().copied().filter(|&s| s <= max_seconds) {
+                let interest = compound_interest(principal, rate, seconds, Compounding::Continuous).unwrap();
+                let exact = BigUint::from(principal) * (reference_exp(INTEREST_RATE_TENTHBPS, seconds) - reference_scale());
+
+                // Never above the exact interest, and at most one unit below it
+                assert!(BigUint::from(interest) * reference_scale() <= exact,
+                    "principal {} seconds {} rounds against the bank", principal, seconds);
+                assert!(BigUint::from(interest + 1) >= exact / reference_scale(),
+                    "principal {} seconds {} is more than one unit short", principal, seconds);
+            }
+        }
+
+        // Rounded up factors bound the exact value from above
+        for seconds in [1, 1_000, 13_159] {
+            let up = exp_ray(rate * seconds as u128, Rounding::Up).unwrap();
+            assert!(BigUint::from(up) * BigUint::from(10u32).pow(33) >= reference_exp(INTEREST_RATE_TENTHBPS, seconds));
+        }
+        assert_eq!(exp_ray(0, Rounding::Up), Some(RAY));
+        assert_eq!(exp_ray(100 * RAY, Rounding::Down), None);
+    }
+
+    #[test]
+    fn test_conventions_are_ordered() {
+        let rate = tenthbps_to_ray(INTEREST_RATE_TENTHBPS);
+        let interest = |seconds, compounding| compound_interest(MAX_USER_DEPOSIT, rate, seconds, compounding).unwrap();
+
+        // More frequent compounding never pays less
+        for seconds in [1, 60, 3_600, 10_000] {
+            assert!(interest(seconds, Compounding::Simple) <= interest(seconds, Compounding::Daily));
+            assert!(interest(seconds, Compounding::Daily) <= interest(seconds, Compounding::PerSecond));
+            assert!(interest(seconds, Compounding::PerSecond) <= interest(seconds, Compounding::Continuous));
+        }
+        assert!(interest(10_000, Compounding::Simple) < interest(10_000, Compounding::PerSecond));
+        assert!(interest(10_000, Compounding::PerSecond) < interest(10_000, Compounding::Continuous));
+    }
+
+    #[test]
+    fn test_max_seconds_respects_convention() {
+        let rate = tenthbps_to_ray(INTEREST_RATE_TENTHBPS);
+        let conventions = [Compounding::Simple, Compounding::Daily, Compounding::PerSecond, Compounding::Continuous];
+        let solved: Vec<u64> = conventions.iter()
+            .map(|&compounding| max_compounding_seconds(MAX_USER_DEPOSIT, rate, MAX_RESERVE_TOKEN_AMOUNT, compounding))
+            .collect();
+
+        // Faster compounding reaches the reserve maximum sooner
+        assert!(solved.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", solved);
+
+        // 100,000 * (1 + 35e-5 * seconds) <= 10,000,000
+        assert_eq!(solved[0], 282_857);
+
+        for (&compounding, &seconds) in conventions.iter().zip(&solved) {
+            let balance = |seconds| MAX_USER_DEPOSIT + compound_interest(MAX_USER_DEPOSIT, rate, seconds, compounding).unwrap();
+            assert!(balance(seconds) <= MAX_RESERVE_TOKEN_AMOUNT, "{:?}", compounding);
+            assert!(balance(seconds + 2) > MAX_RESERVE_TOKEN_AMOUNT, "{:?}", compounding);
+        }
+    }
+
+    #[test]
+    fn test_annual_rates_convert_to_per_second() {
+        // 10% APR: 0.1 / 31,536,000 per second
+        let apr = annual_bps_to_per_second_ray(1_000, RateBasis::Apr, Compounding::PerSecond).unwrap();
+        assert_eq!(apr, 1_000 * BPS_RAY / SECONDS_PER_YEAR as u128);
+
+        // Under simple interest the APY and APR agree
+        assert_eq!(annual_bps_to_per_second_ray(1_000, RateBasis::Apy, Compounding::Simple), Some(apr));
+
+        // A 10% APY earns 10% over a year (never more, and short by at most 1e-8 of it) under every convention
+        for compounding in [Compounding::Simple, Compounding::Daily, Compounding::PerSecond, Compounding::Continuous] {
+            let rate = annual_bps_to_per_second_ray(1_000, RateBasis::Apy, compounding).unwrap();
+            assert!(rate <= apr, "{:?}", compounding);
+            let interest = compound_interest(1_000_000_000_000, rate, SECONDS_PER_YEAR, compounding).unwrap();
+            assert!(interest <= 100_000_000_000, "{:?}: {}", compounding, interest);
+            assert!(interest >= 100_000_000_000 - 1_000, "{:?}: {}", compounding, interest);
+
+            // One more unit of rate would exceed the quote
+            let over = compounding.factor(rate + 1, SECONDS_PER_YEAR, Rounding::Up).unwrap();
+            assert!(over > RAY + 1_000 * BPS_RAY, "{:?}", compounding);
+        }
+
+        assert_eq!(annual_bps_to_per_second_ray(0, RateBasis::Apy, Compounding::PerSecond), Some(0));
+    }
+
+    /// Largest rate whose yearly growth fits the APY, found by bisection over every candidate
+    fn reference_apy_rate(annual_bps: u64, compounding: Compounding) -> u128 {
+        let yearly_factor = RAY + annual_bps as u128 * BPS_RAY;
+        let fits = |rate| compounding.factor(rate, SECONDS_PER_YEAR, Rounding::Up).unwrap() <= yearly_factor;
+        let (mut lo, mut hi) = (0, annual_bps as u128 * BPS_RAY / SECONDS_PER_YEAR as u128 + 1);
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if fits(mid) { lo = mid } else { hi = mid }
+        }
+        lo
+    }
+
+    #[test]
+    fn test_apy_conversion_is_exact_within_bounded_corrections() {
+        let conventions = [Compounding::Simple, Compounding::Daily, Compounding::PerSecond, Compounding::Continuous];
+        let rates = (1..=MAX_ANNUAL_RATE_BPS).step_by(997).chain([1, 2, 500, 10_000, MAX_ANNUAL_RATE_BPS]);
+        for annual_bps in rates {
+            for compounding in conventions {
+                let (rate, corrections) = apy_to_per_second_ray(annual_bps, compounding).unwrap();
+                assert_eq!(rate, reference_apy_rate(annual_bps, compounding), "{} bps {:?}", annual_bps, compounding);
+
+                // The estimate lands next to the answer, so a couple of factor evaluations confirm it
+                assert!(corrections <= 2, "{} bps {:?}: {} corrections", annual_bps, compounding, corrections);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ln_matches_known_values() {
+        assert_eq!(ln_ray(RAY), Some(0));
+        let ln = |x| ln_ray(x).unwrap() as i128;
+        assert!((ln(2 * RAY) - LN_2_RAY as i128).abs() <= 2);
+        // ln(11) = 2.397895272798370544061943577...
+        assert!((ln(11 * RAY) - 2_397_895_272_798_370_544_061_943_577).abs() <= 64);
+        assert_eq!(ln_ray(RAY - 1), None);
+        assert_eq!(ray_div(RAY, 3 * RAY), Some(RAY / 3));
+    }
+}
@@ -8,6 +8,8 @@ import {
   mintTo,
   transfer,
   createAccount,
+  createMint,
+  getMint,
 } from "@solana/spl-token";
 import { rpc, token } from "@project-serum/anchor/dist/cjs/utils";
 const fs = require("fs");
@@ -88,198 +90,199 @@ describe("genesys-banking", () => {
     )
   );
 
-  it("Vault is initialized!", async () => {
-    let [vaultInfo, infoBump] = await anchor.web3.PublicKey.findProgramAddress(
+  // Mint of the external vault (created in the tests, owned by the user)
+  const externalMint = anchor.web3.Keypair.generate();
+
+  // Vault parameters
+  const maxTokens = 10_000_000;
+  const annualRateBps = 500;
+  const maxUserDeposit = 100_000;
+  const earlyWithdrawalPenaltyBps = 500;
+  const reserveBuffer = 1;
+  const postMaturityRateBps = 200;
+  const maxPostMaturitySeconds = 30 * 86_400;
+
+  // Vault parameters as passed to initialize and updateVaultConfig, with the rate quoted as an APY
+  function vaultConfig(rateBps: number, productsOnly = false) {
+    return {
+      maxTokens: new anchor.BN(maxTokens),
+      annualRateBps: new anchor.BN(rateBps),
+      rateBasis: { apy: {} },
+      maxUserDeposit: new anchor.BN(maxUserDeposit),
+      earlyWithdrawalPenaltyBps: new anchor.BN(earlyWithdrawalPenaltyBps),
+      reserveBuffer: new anchor.BN(reserveBuffer),
+      postMaturityRateBps: new anchor.BN(postMaturityRateBps),
+      maxPostMaturitySeconds: new anchor.BN(maxPostMaturitySeconds),
+      productsOnly,
+    };
+  }
+
+  // Index of the deposit box used by the deposit/withdraw tests
+  const depositIndex = 0;
+
+  async function findVaultInfo(
+    mint: anchor.web3.PublicKey
+  ): Promise<[anchor.web3.PublicKey, number]> {
+    return await anchor.web3.PublicKey.findProgramAddress(
       [
         Buffer.from(
           anchor.utils.bytes.utf8.encode(programConstants["VAULT_INFO_SEED"])
         ),
+        mint.toBuffer(),
       ],
       program.programId
     );
+  }
 
-    let [tokenVault, vaultBump] =
-      await anchor.web3.PublicKey.findProgramAddress(
-        [
-          Buffer.from(
-            anchor.utils.bytes.utf8.encode(programConstants["TOKEN_VAULT_SEED"])
-          ),
-        ],
-        program.programId
-      );
-
-    console.log("admin:", vaultAdmin.publicKey.toString());
-    console.log("mint:", tokenMint.publicKey.toString());
-    console.log("user:", user.publicKey.toString());
-    console.log("token program:", TOKEN_PROGRAM_ID.toString());
-    console.log(
-      "system program:",
-      anchor.web3.SystemProgram.programId.toString()
-    );
-    console.log(
-      "vaultInfo(seed=",
-      programConstants["VAULT_INFO_SEED"],
-      "):",
-      vaultInfo.toString()
-    );
-    console.log(
-      "tokenVault(seed =",
-      programConstants["TOKEN_VAULT_SEED"],
-      "):",
-      tokenVault.toString()
+  async function findVaultRoles(
+    vaultInfo: anchor.web3.PublicKey
+  ): Promise<[anchor.web3.PublicKey, number]> {
+    return await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(
+          anchor.utils.bytes.utf8.encode(programConstants["VAULT_ROLES_SEED"])
+        ),
+        vaultInfo.toBuffer(),
+      ],
+      program.programId
     );
+  }
 
-    // Add your test here.
-    const tx = await program.rpc.initialize({
-      accounts: {
-        vaultInfo: vaultInfo,
-        tokenVault: tokenVault,
-        tokenMint: tokenMint.publicKey,
-        vaultAdmin: vaultAdmin.publicKey,
-        systemProgram: anchor.web3.SystemProgram.programId,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
-      },
-      signers: [vaultAdmin, tokenMint],
-    });
-    console.log("Your transaction signature", tx);
-  });
-
-  it("Vault admin refreshed FEET reserve account!", async () => {
-    // Grab Vault Info PDA + bump
-    let [vaultInfo, infoBump] = await anchor.web3.PublicKey.findProgramAddress(
+  async function findQueuedClaim(
+    vaultInfo: anchor.web3.PublicKey,
+    sequence: number
+  ): Promise<[anchor.web3.PublicKey, number]> {
+    return await anchor.web3.PublicKey.findProgramAddress(
       [
         Buffer.from(
-          anchor.utils.bytes.utf8.encode(programConstants["VAULT_INFO_SEED"])
+          anchor.utils.bytes.utf8.encode(programConstants["QUEUED_CLAIM_SEED"])
         ),
+        vaultInfo.toBuffer(),
+        new anchor.BN(sequence).toArrayLike(Buffer, "le", 8),
       ],
       program.programId
     );
+  }
 
-    // Grab token vault/reseve PDA + bump
-    let [tokenVault, reserveBump] =
-      await anchor.web3.PublicKey.findProgramAddress(
-        [
-          Buffer.from(
-            anchor.utils.bytes.utf8.encode(programConstants["TOKEN_VAULT_SEED"])
-          ),
-        ],
-        program.programId
-      );
-
-    let prevTokenVaultBalance =
-      await provider.connection.getTokenAccountBalance(tokenVault);
-    const tx = await program.rpc.refreshReserve(infoBump, reserveBump, {
-      accounts: {
-        vaultInfo: vaultInfo,
-        tokenVault: tokenVault,
-        tokenMint: tokenMint.publicKey,
-        vaultAdmin: vaultAdmin.publicKey,
-        systemProgram: anchor.web3.SystemProgram.programId,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
-        program: program.programId,
-      },
-      signers: [vaultAdmin],
-    });
-    console.log("Your transaction signature", tx);
-
-    let finalTokenVaultBalance =
-      await provider.connection.getTokenAccountBalance(tokenVault);
-    console.log("prev token Balance =", prevTokenVaultBalance.value.amount);
-    console.log("post token Balance =", finalTokenVaultBalance.value.amount);
-  });
-
-  it("User deposits FEET with 3 sec lockout!", async () => {
-    // airdrop user some SOL
-    const transfer_tx = new anchor.web3.Transaction().add(
-      anchor.web3.SystemProgram.transfer({
-        fromPubkey: vaultAdmin.publicKey,
-        toPubkey: user.publicKey,
-        lamports: 1 * anchor.web3.LAMPORTS_PER_SOL,
-      })
+  async function findRateTiers(
+    vaultInfo: anchor.web3.PublicKey
+  ): Promise<[anchor.web3.PublicKey, number]> {
+    return await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(
+          anchor.utils.bytes.utf8.encode(programConstants["RATE_TIERS_SEED"])
+        ),
+        vaultInfo.toBuffer(),
+      ],
+      program.programId
     );
+  }
 
-    const signature = await anchor.web3.sendAndConfirmTransaction(
-      provider.connection,
-      transfer_tx,
-      [vaultAdmin]
+  async function findProduct(
+    vaultInfo: anchor.web3.PublicKey,
+    productId: number
+  ): Promise<[anchor.web3.PublicKey, number]> {
+    return await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(
+          anchor.utils.bytes.utf8.encode(programConstants["PRODUCT_SEED"])
+        ),
+        vaultInfo.toBuffer(),
+        new anchor.BN(productId).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
     );
-    console.log("SOL xfer to user signature:", signature);
-    let solBalance = await provider.connection.getBalance(user.publicKey);
-    console.log("user SOL lamports balance is ", solBalance);
+  }
 
-    // now airdrop some SPL
-    // find user ATA
-    let userATA = await findAssociatedTokenAddress(
-      user.publicKey,
-      tokenMint.publicKey
-    );
-    console.log("user FEET ATA is", userATA.toString());
-    // create ATA
-    let created_ATA = await createAccount(
-      provider.connection,
-      user,
-      tokenMint.publicKey,
-      user.publicKey
+  async function findTokenVault(
+    mint: anchor.web3.PublicKey
+  ): Promise<[anchor.web3.PublicKey, number]> {
+    return await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(
+          anchor.utils.bytes.utf8.encode(programConstants["TOKEN_VAULT_SEED"])
+        ),
+        mint.toBuffer(),
+      ],
+      program.programId
     );
-    console.log("account created (should match):", created_ATA.toString());
-    let airdrop_tx = await mintTo(
-      provider.connection,
-      user,
-      tokenMint.publicKey,
-      userATA, //user.publicKey,
-      vaultAdmin,
-      100000
+  }
+
+  async function findUserProfile(
+    depositor: anchor.web3.PublicKey
+  ): Promise<[anchor.web3.PublicKey, number]> {
+    return await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(
+          anchor.utils.bytes.utf8.encode(programConstants["USER_PROFILE_SEED"])
+        ),
+        (await findVaultInfo(tokenMint.publicKey))[0].toBuffer(),
+        depositor.toBuffer(),
+      ],
+      program.programId
     );
-    console.log("airdrop_tx =", airdrop_tx);
+  }
 
-    let tokenBalance = await provider.connection.getTokenAccountBalance(
-      userATA
+  async function findDepositInfo(
+    depositor: anchor.web3.PublicKey,
+    index: number,
+    mint: anchor.web3.PublicKey = tokenMint.publicKey
+  ): Promise<[anchor.web3.PublicKey, number]> {
+    return await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(
+          anchor.utils.bytes.utf8.encode(programConstants["USER_DEPOSIT_INFO"])
+        ),
+        (await findVaultInfo(mint))[0].toBuffer(),
+        depositor.toBuffer(),
+        new anchor.BN(index).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
     );
-    console.log("user FEET balance is ", tokenBalance.value.amount);
+  }
 
-    let [vaultInfo, infoBump] = await anchor.web3.PublicKey.findProgramAddress(
+  async function findUserVault(
+    depositor: anchor.web3.PublicKey,
+    index: number,
+    mint: anchor.web3.PublicKey = tokenMint.publicKey
+  ): Promise<[anchor.web3.PublicKey, number]> {
+    return await anchor.web3.PublicKey.findProgramAddress(
       [
         Buffer.from(
-          anchor.utils.bytes.utf8.encode(programConstants["VAULT_INFO_SEED"])
+          anchor.utils.bytes.utf8.encode(programConstants["USER_VAULT_SEED"])
         ),
+        (await findVaultInfo(mint))[0].toBuffer(),
+        depositor.toBuffer(),
+        new anchor.BN(index).toArrayLike(Buffer, "le", 8),
       ],
       program.programId
     );
+  }
 
-    let [tokenVault, reserveBump] =
-      await anchor.web3.PublicKey.findProgramAddress(
-        [
-          Buffer.from(
-            anchor.utils.bytes.utf8.encode(programConstants["TOKEN_VAULT_SEED"])
-          ),
-        ],
-        program.programId
-      );
+  async function depositFeet(
+    index: number,
+    secondsLocked: number,
+    amount: number
+  ) {
+    let userATA = await findAssociatedTokenAddress(
+      user.publicKey,
+      tokenMint.publicKey
+    );
 
-    let [depositInfo, depositInfoBump] =
-      await anchor.web3.PublicKey.findProgramAddress(
-        [
-          Buffer.from(
-            anchor.utils.bytes.utf8.encode(
-              programConstants["USER_DEPOSIT_INFO"]
-            )
-          ),
-          user.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
-    let [userVault, userVaultBump] =
-      await anchor.web3.PublicKey.findProgramAddress(
-        [
-          Buffer.from(
-            anchor.utils.bytes.utf8.encode(programConstants["USER_VAULT_SEED"])
-          ),
-          user.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+
+    let [tokenVault, reserveBump] = await findTokenVault(tokenMint.publicKey);
+
+    let [userProfile] = await findUserProfile(user.publicKey);
+    let [depositInfo, depositInfoBump] = await findDepositInfo(
+      user.publicKey,
+      index
+    );
+    let [userVault, userVaultBump] = await findUserVault(
+      user.publicKey,
+      index
+    );
+    let [rateTiers] = await findRateTiers(vaultInfo);
     console.log("user vault is", userVault.toString());
 
     let tx = await program.rpc.deposit(
@@ -287,12 +290,15 @@ describe("genesys-banking", () => {
       infoBump,
       depositInfoBump,
       userVaultBump,
-      new anchor.BN(3),
-      new anchor.BN(100000),
+      new anchor.BN(secondsLocked),
+      new anchor.BN(amount),
       {
         accounts: {
+          userProfile: userProfile,
           depositInfo: depositInfo,
           vaultInfo: vaultInfo,
+          tokenVault: tokenVault,
+          rateTiers: rateTiers,
           userVault: userVault,
           tokenMint: tokenMint.publicKey,
           vaultAdmin: vaultAdmin.publicKey,
@@ -306,74 +312,48 @@ describe("genesys-banking", () => {
         signers: [user],
       }
     );
-    let vaultBalance = await provider.connection.getTokenAccountBalance(
-      userVault
-    );
-    console.log("Asserting vault balance equals deposited amount");
-    assert(vaultBalance.value.amount == "100000");
-  });
-
-  it("User withdraws FEET after 4 sec of waiting!", async () => {
-    await new Promise((f) => setTimeout(f, 4000));
+    console.log("Your transaction signature", tx);
+  }
 
+  async function withdrawFeet(
+    index: number,
+    product: anchor.web3.PublicKey | null = null
+  ) {
     let userATA = await findAssociatedTokenAddress(
       user.publicKey,
       tokenMint.publicKey
     );
 
-    let [vaultInfo, infoBump] = await anchor.web3.PublicKey.findProgramAddress(
-      [
-        Buffer.from(
-          anchor.utils.bytes.utf8.encode(programConstants["VAULT_INFO_SEED"])
-        ),
-      ],
-      program.programId
-    );
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
 
-    let [tokenVault, reserveBump] =
-      await anchor.web3.PublicKey.findProgramAddress(
-        [
-          Buffer.from(
-            anchor.utils.bytes.utf8.encode(programConstants["TOKEN_VAULT_SEED"])
-          ),
-        ],
-        program.programId
-      );
+    let [tokenVault, reserveBump] = await findTokenVault(tokenMint.publicKey);
 
-    let [depositInfo, depositInfoBump] =
-      await anchor.web3.PublicKey.findProgramAddress(
-        [
-          Buffer.from(
-            anchor.utils.bytes.utf8.encode(
-              programConstants["USER_DEPOSIT_INFO"]
-            )
-          ),
-          user.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
-    let [userVault, userVaultBump] =
-      await anchor.web3.PublicKey.findProgramAddress(
-        [
-          Buffer.from(
-            anchor.utils.bytes.utf8.encode(programConstants["USER_VAULT_SEED"])
-          ),
-          user.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+    let [depositInfo, depositInfoBump] = await findDepositInfo(
+      user.publicKey,
+      index
+    );
+    let [userVault, userVaultBump] = await findUserVault(
+      user.publicKey,
+      index
+    );
+
+    // Interest the reserve cannot cover is queued behind the vault's last claim
+    let { queueTail } = await program.account.vaultInfo.fetch(vaultInfo);
+    let [queuedClaim] = await findQueuedClaim(vaultInfo, queueTail.toNumber());
 
     let tx = await program.rpc.withdraw(
       reserveBump,
       infoBump,
       depositInfoBump,
       userVaultBump,
+      new anchor.BN(index),
       {
         accounts: {
           depositInfo: depositInfo,
           vaultInfo: vaultInfo,
           userVault: userVault,
           tokenVault: tokenVault,
+          queuedClaim: queuedClaim,
           tokenMint: tokenMint.publicKey,
           vaultAdmin: vaultAdmin.publicKey,
           depositor: user.publicKey,
@@ -384,13 +364,1377 @@ describe("genesys-banking", () => {
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           program: program.programId,
         },
+        // Boxes opened through a product release it on withdraw
+        remainingAccounts: product
+          ? [{ pubkey: product, isWritable: true, isSigner: false }]
+          : [],
         signers: [user],
       }
     );
+    console.log("Your transaction signature", tx);
+  }
 
-    let userBalance = await provider.connection.getTokenAccountBalance(userATA);
-    console.log("Asserting user balance is greater than deposited amount");
-    console.log("new user balance is", parseInt(userBalance.value.amount));
-    assert(parseInt(userBalance.value.amount) > 100000);
+  async function withdrawEarlyFeet(index: number) {
+    let userATA = await findAssociatedTokenAddress(
+      user.publicKey,
+      tokenMint.publicKey
+    );
+
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+
+    let [tokenVault, reserveBump] = await findTokenVault(tokenMint.publicKey);
+
+    let [depositInfo, depositInfoBump] = await findDepositInfo(
+      user.publicKey,
+      index
+    );
+    let [userVault, userVaultBump] = await findUserVault(
+      user.publicKey,
+      index
+    );
+
+    let tx = await program.rpc.withdrawEarly(
+      reserveBump,
+      infoBump,
+      depositInfoBump,
+      userVaultBump,
+      new anchor.BN(index),
+      {
+        accounts: {
+          depositInfo: depositInfo,
+          vaultInfo: vaultInfo,
+          userVault: userVault,
+          tokenVault: tokenVault,
+          tokenMint: tokenMint.publicKey,
+          depositor: user.publicKey,
+          depositorTokenAccount: userATA,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        },
+        signers: [user],
+      }
+    );
+    console.log("Your transaction signature", tx);
+  }
+
+  async function topUpFeet(index: number, amount: number) {
+    let userATA = await findAssociatedTokenAddress(
+      user.publicKey,
+      tokenMint.publicKey
+    );
+
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+
+    let [tokenVault, reserveBump] = await findTokenVault(tokenMint.publicKey);
+
+    let [depositInfo, depositInfoBump] = await findDepositInfo(
+      user.publicKey,
+      index
+    );
+    let [userVault, userVaultBump] = await findUserVault(
+      user.publicKey,
+      index
+    );
+
+    let tx = await program.rpc.topUp(
+      reserveBump,
+      infoBump,
+      depositInfoBump,
+      userVaultBump,
+      new anchor.BN(index),
+      new anchor.BN(amount),
+      {
+        accounts: {
+          depositInfo: depositInfo,
+          vaultInfo: vaultInfo,
+          userVault: userVault,
+          tokenVault: tokenVault,
+          tokenMint: tokenMint.publicKey,
+          depositor: user.publicKey,
+          depositorTokenAccount: userATA,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [user],
+      }
+    );
+    console.log("Your transaction signature", tx);
+  }
+
+  it("Admin creates FEET mint and airdrops to user!", async () => {
+    // The admin creates the mint; its mint authority is handed to the vault on initialize
+    let mint = await createMint(
+      provider.connection,
+      vaultAdmin,
+      vaultAdmin.publicKey,
+      vaultAdmin.publicKey,
+      0,
+      tokenMint
+    );
+    console.log("mint created (should match):", mint.toString());
+
+    // airdrop user some SOL
+    const transfer_tx = new anchor.web3.Transaction().add(
+      anchor.web3.SystemProgram.transfer({
+        fromPubkey: vaultAdmin.publicKey,
+        toPubkey: user.publicKey,
+        lamports: 1 * anchor.web3.LAMPORTS_PER_SOL,
+      })
+    );
+
+    const signature = await anchor.web3.sendAndConfirmTransaction(
+      provider.connection,
+      transfer_tx,
+      [vaultAdmin]
+    );
+    console.log("SOL xfer to user signature:", signature);
+    let solBalance = await provider.connection.getBalance(user.publicKey);
+    console.log("user SOL lamports balance is ", solBalance);
+
+    // now airdrop some SPL
+    // find user ATA
+    let userATA = await findAssociatedTokenAddress(
+      user.publicKey,
+      tokenMint.publicKey
+    );
+    console.log("user FEET ATA is", userATA.toString());
+    // create ATA
+    let created_ATA = await createAccount(
+      provider.connection,
+      user,
+      tokenMint.publicKey,
+      user.publicKey
+    );
+    console.log("account created (should match):", created_ATA.toString());
+    let airdrop_tx = await mintTo(
+      provider.connection,
+      user,
+      tokenMint.publicKey,
+      userATA, //user.publicKey,
+      vaultAdmin,
+      200000
+    );
+    console.log("airdrop_tx =", airdrop_tx);
+
+    let tokenBalance = await provider.connection.getTokenAccountBalance(
+      userATA
+    );
+    console.log("user FEET balance is ", tokenBalance.value.amount);
+  });
+
+  it("Vault is initialized over the admin's mint and takes over its mint authority!", async () => {
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+
+    let [tokenVault, vaultBump] = await findTokenVault(tokenMint.publicKey);
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+
+    console.log("admin:", vaultAdmin.publicKey.toString());
+    console.log("mint:", tokenMint.publicKey.toString());
+    console.log("user:", user.publicKey.toString());
+    console.log("token program:", TOKEN_PROGRAM_ID.toString());
+    console.log(
+      "system program:",
+      anchor.web3.SystemProgram.programId.toString()
+    );
+    console.log(
+      "vaultInfo(seed=",
+      programConstants["VAULT_INFO_SEED"],
+      "):",
+      vaultInfo.toString()
+    );
+    console.log(
+      "tokenVault(seed =",
+      programConstants["TOKEN_VAULT_SEED"],
+      "):",
+      tokenVault.toString()
+    );
+
+    // Add your test here.
+    const tx = await program.rpc.initialize(
+      vaultConfig(annualRateBps),
+      false,
+      {
+        accounts: {
+          vaultInfo: vaultInfo,
+          vaultRoles: vaultRoles,
+          tokenVault: tokenVault,
+          tokenMint: tokenMint.publicKey,
+          vaultAdmin: vaultAdmin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        signers: [vaultAdmin],
+      }
+    );
+    console.log("Your transaction signature", tx);
+
+    let info = await program.account.vaultInfo.fetch(vaultInfo);
+    console.log("Asserting vault parameters were stored");
+    assert(info.maxTokens.toNumber() == maxTokens);
+    assert(info.annualRateBps.toNumber() == annualRateBps);
+    assert(info.rateBasis.apy);
+    console.log("Asserting the APY was converted to a per-second rate");
+    assert(!info.ratePerSecond.isZero());
+    console.log("Asserting matured boxes keep earning for the post-maturity window");
+    assert(info.postMaturityRateBps.toNumber() == postMaturityRateBps);
+    assert(info.maxPostMaturitySeconds.toNumber() == maxPostMaturitySeconds);
+    assert(info.postMaturityRatePerSecond.lt(info.ratePerSecond));
+    assert(info.maxUserDeposit.toNumber() == maxUserDeposit);
+    assert(info.decimals == 0);
+    assert(
+      info.earlyWithdrawalPenaltyBps.toNumber() == earlyWithdrawalPenaltyBps
+    );
+
+    assert(info.queueHead.toNumber() == 0);
+    assert(info.queueTail.toNumber() == 0);
+
+    let mint = await getMint(provider.connection, tokenMint.publicKey);
+    console.log("Asserting mint authority was handed to the vault");
+    assert(mint.mintAuthority.equals(vaultInfo));
+
+    // The mint already had supply in circulation, as a legacy vault's would; it stays put
+    assert(Number(mint.supply) == 200000);
+    console.log("Asserting the admin can no longer mint");
+    let userATA = await findAssociatedTokenAddress(
+      user.publicKey,
+      tokenMint.publicKey
+    );
+    await assert.rejects(
+      mintTo(provider.connection, vaultAdmin, tokenMint.publicKey, userATA, vaultAdmin, 1)
+    );
+  });
+
+  it("Vault admin refreshed FEET reserve account!", async () => {
+    // Grab Vault Info PDA + bump
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+
+    // Grab token vault/reseve PDA + bump
+    let [tokenVault, reserveBump] = await findTokenVault(tokenMint.publicKey);
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+
+    let prevTokenVaultBalance =
+      await provider.connection.getTokenAccountBalance(tokenVault);
+    // Queued claims would be settled from the remaining accounts (there are none here)
+    const tx = await program.rpc.refreshReserve(infoBump, reserveBump, {
+      accounts: {
+        vaultInfo: vaultInfo,
+        vaultRoles: vaultRoles,
+        tokenVault: tokenVault,
+        tokenMint: tokenMint.publicKey,
+        operator: vaultAdmin.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        program: program.programId,
+      },
+      signers: [vaultAdmin],
+    });
+    console.log("Your transaction signature", tx);
+
+    let finalTokenVaultBalance =
+      await provider.connection.getTokenAccountBalance(tokenVault);
+    console.log("prev token Balance =", prevTokenVaultBalance.value.amount);
+    console.log("post token Balance =", finalTokenVaultBalance.value.amount);
+  });
+
+  it("Admin cannot sweep out a refreshed reserve!", async () => {
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+    let [tokenVault] = await findTokenVault(tokenMint.publicKey);
+    let userATA = await findAssociatedTokenAddress(
+      user.publicKey,
+      tokenMint.publicKey
+    );
+
+    // The minted reserve is all excess, but none of it was funded
+    let reserveBalance = await provider.connection.getTokenAccountBalance(
+      tokenVault
+    );
+    assert(parseInt(reserveBalance.value.amount) > reserveBuffer);
+    await assert.rejects(
+      program.rpc.sweepReserve(infoBump, new anchor.BN(1), {
+        accounts: {
+          vaultInfo: vaultInfo,
+          tokenVault: tokenVault,
+          tokenMint: tokenMint.publicKey,
+          vaultAdmin: vaultAdmin.publicKey,
+          destination: userATA,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [vaultAdmin],
+      })
+    );
+  });
+
+  it("Risk manager updates vault config with an APY and post-maturity accrual!", async () => {
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+
+    const tx = await program.rpc.updateVaultConfig(
+      infoBump,
+      vaultConfig(annualRateBps + 100),
+      {
+        accounts: {
+          vaultInfo: vaultInfo,
+          vaultRoles: vaultRoles,
+          tokenMint: tokenMint.publicKey,
+          riskManager: vaultAdmin.publicKey,
+        },
+        signers: [vaultAdmin],
+      }
+    );
+    console.log("Your transaction signature", tx);
+
+    let info = await program.account.vaultInfo.fetch(vaultInfo);
+    console.log("Asserting the new interest rate was stored");
+    assert(info.annualRateBps.toNumber() == annualRateBps + 100);
+  });
+
+  it("Non-risk manager cannot update vault config!", async () => {
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+
+    await assert.rejects(
+      program.rpc.updateVaultConfig(
+        infoBump,
+        vaultConfig(annualRateBps),
+        {
+          accounts: {
+            vaultInfo: vaultInfo,
+            vaultRoles: vaultRoles,
+            tokenMint: tokenMint.publicKey,
+            riskManager: user.publicKey,
+          },
+          signers: [user],
+        }
+      )
+    );
+  });
+
+  it("User deposits 100,000 FEET with 3 sec lockout!", async () => {
+    await depositFeet(depositIndex, 3, 100000);
+
+    let [vaultInfo] = await findVaultInfo(tokenMint.publicKey);
+    let vault = await program.account.vaultInfo.fetch(vaultInfo);
+    let [depositInfo] = await findDepositInfo(user.publicKey, depositIndex);
+    let info = await program.account.depositInfo.fetch(depositInfo);
+    console.log("Asserting the box locked in the vault's current terms");
+    assert(info.ratePerSecond.eq(vault.ratePerSecond));
+    assert(info.postMaturityRatePerSecond.eq(vault.postMaturityRatePerSecond));
+    assert(info.maxPostMaturitySeconds.toNumber() == maxPostMaturitySeconds);
+    assert(info.compounding.perSecond !== undefined);
+
+    let [userVault] = await findUserVault(user.publicKey, depositIndex);
+    let vaultBalance = await provider.connection.getTokenAccountBalance(
+      userVault
+    );
+    console.log("Asserting vault balance equals deposited amount");
+    assert(vaultBalance.value.amount == "100000");
+  });
+
+  it("User withdraws FEET plus interest after 4 sec of waiting!", async () => {
+    await new Promise((f) => setTimeout(f, 4000));
+
+    let userATA = await findAssociatedTokenAddress(
+      user.publicKey,
+      tokenMint.publicKey
+    );
+
+    await withdrawFeet(depositIndex);
+
+    let userBalance = await provider.connection.getTokenAccountBalance(userATA);
+    console.log("Asserting user balance is greater than airdropped amount");
+    console.log("new user balance is", parseInt(userBalance.value.amount));
+    assert(parseInt(userBalance.value.amount) >= 200000);
+  });
+
+  it("Withdraw closed the deposit box accounts!", async () => {
+    let [depositInfo] = await findDepositInfo(user.publicKey, depositIndex);
+    let [userVault] = await findUserVault(user.publicKey, depositIndex);
+
+    console.log("Asserting deposit info and user vault are closed");
+    assert(
+      (await provider.connection.getAccountInfo(depositInfo)) == null
+    );
+    assert((await provider.connection.getAccountInfo(userVault)) == null);
+  });
+
+  it("User deposits 50,000 FEET into a new box after withdrawing!", async () => {
+    let [userProfile] = await findUserProfile(user.publicKey);
+    let profile = await program.account.userProfile.fetch(userProfile);
+    console.log("Asserting the next deposit box is the second one");
+    assert(profile.depositCount.toNumber() == depositIndex + 1);
+
+    await depositFeet(depositIndex + 1, 3, 50000);
+
+    let [userVault] = await findUserVault(user.publicKey, depositIndex + 1);
+    let vaultBalance = await provider.connection.getTokenAccountBalance(
+      userVault
+    );
+    console.log("Asserting vault balance equals deposited amount");
+    assert(vaultBalance.value.amount == "50000");
+  });
+
+  it("User tops up the second deposit box with 40,000 FEET!", async () => {
+    // Let some interest accrue before topping up
+    await new Promise((f) => setTimeout(f, 2000));
+
+    let [depositInfo] = await findDepositInfo(user.publicKey, depositIndex + 1);
+    let [userVault] = await findUserVault(user.publicKey, depositIndex + 1);
+    let before = await program.account.depositInfo.fetch(depositInfo);
+
+    await topUpFeet(depositIndex + 1, 40000);
+
+    let after = await program.account.depositInfo.fetch(depositInfo);
+    let vaultBalance = await provider.connection.getTokenAccountBalance(
+      userVault
+    );
+    console.log("Asserting principal is blended with accrued interest");
+    assert(after.depositLamports.toNumber() >= 90000);
+    assert(vaultBalance.value.amount == after.depositLamports.toString());
+    console.log("Asserting the lock restarted");
+    assert(after.depositTime.toNumber() > before.depositTime.toNumber());
+    assert(after.secondsLocked.toNumber() == before.secondsLocked.toNumber());
+  });
+
+  it("User withdraws the second deposit box!", async () => {
+    await new Promise((f) => setTimeout(f, 4000));
+
+    let userATA = await findAssociatedTokenAddress(
+      user.publicKey,
+      tokenMint.publicKey
+    );
+    let prevUserBalance = await provider.connection.getTokenAccountBalance(
+      userATA
+    );
+
+    await withdrawFeet(depositIndex + 1);
+
+    let userBalance = await provider.connection.getTokenAccountBalance(userATA);
+    console.log("Asserting user earned interest on the second box");
+    assert(
+      parseInt(userBalance.value.amount) >=
+        parseInt(prevUserBalance.value.amount) + 90000
+    );
+
+    let [depositInfo] = await findDepositInfo(user.publicKey, depositIndex + 1);
+    assert(
+      (await provider.connection.getAccountInfo(depositInfo)) == null
+    );
+  });
+
+  it("User withdraws a third deposit box early, paying the penalty to the reserve!", async () => {
+    await depositFeet(depositIndex + 2, 600, 10000);
+
+    let userATA = await findAssociatedTokenAddress(
+      user.publicKey,
+      tokenMint.publicKey
+    );
+    let [tokenVault] = await findTokenVault(tokenMint.publicKey);
+    let prevUserBalance = await provider.connection.getTokenAccountBalance(
+      userATA
+    );
+    let prevReserveBalance = await provider.connection.getTokenAccountBalance(
+      tokenVault
+    );
+
+    await withdrawEarlyFeet(depositIndex + 2);
+
+    // 5% of 10,000 is forfeited to the reserve, and no interest is paid
+    let penalty = (10000 * earlyWithdrawalPenaltyBps) / 10000;
+    let userBalance = await provider.connection.getTokenAccountBalance(userATA);
+    let reserveBalance = await provider.connection.getTokenAccountBalance(
+      tokenVault
+    );
+    console.log("Asserting user got the principal back minus the penalty");
+    assert(
+      parseInt(userBalance.value.amount) ==
+        parseInt(prevUserBalance.value.amount) + 10000 - penalty
+    );
+    console.log("Asserting the penalty went to the reserve");
+    assert(
+      parseInt(reserveBalance.value.amount) ==
+        parseInt(prevReserveBalance.value.amount) + penalty
+    );
+
+    let [depositInfo] = await findDepositInfo(user.publicKey, depositIndex + 2);
+    assert(
+      (await provider.connection.getAccountInfo(depositInfo)) == null
+    );
+  });
+
+  it("Vault is initialized over an external 6 decimal mint!", async () => {
+    // A 6 decimal mint the vault admin does not control (think USDC), although
+    // the admin happens to hold its freeze authority
+    await createMint(
+      provider.connection,
+      vaultAdmin,
+      user.publicKey,
+      vaultAdmin.publicKey,
+      6,
+      externalMint
+    );
+
+    let [vaultInfo] = await findVaultInfo(externalMint.publicKey);
+    let [tokenVault] = await findTokenVault(externalMint.publicKey);
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+
+    const tx = await program.rpc.initialize(
+      vaultConfig(annualRateBps),
+      true,
+      {
+        accounts: {
+          vaultInfo: vaultInfo,
+          vaultRoles: vaultRoles,
+          tokenVault: tokenVault,
+          tokenMint: externalMint.publicKey,
+          vaultAdmin: vaultAdmin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        },
+        signers: [vaultAdmin],
+      }
+    );
+    console.log("Your transaction signature", tx);
+
+    let info = await program.account.vaultInfo.fetch(vaultInfo);
+    console.log("Asserting caps were scaled to base units");
+    assert(info.externalMint);
+    assert(info.decimals == 6);
+    assert(
+      info.maxTokens.eq(new anchor.BN(maxTokens).mul(new anchor.BN(1_000_000)))
+    );
+
+    let mint = await getMint(provider.connection, externalMint.publicKey);
+    console.log("Asserting the mint authority was left untouched");
+    assert(mint.mintAuthority.equals(user.publicKey));
+  });
+
+  it("Reserve of an external mint vault cannot be minted!", async () => {
+    let [vaultInfo, infoBump] = await findVaultInfo(externalMint.publicKey);
+    let [tokenVault, reserveBump] = await findTokenVault(
+      externalMint.publicKey
+    );
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+
+    await assert.rejects(
+      program.rpc.refreshReserve(infoBump, reserveBump, {
+        accounts: {
+          vaultInfo: vaultInfo,
+          vaultRoles: vaultRoles,
+          tokenVault: tokenVault,
+          tokenMint: externalMint.publicKey,
+          operator: vaultAdmin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          program: program.programId,
+        },
+        signers: [vaultAdmin],
+      })
+    );
+  });
+
+  it("Anyone can fund the reserve of an external mint vault!", async () => {
+    let [vaultInfo, infoBump] = await findVaultInfo(externalMint.publicKey);
+    let [tokenVault] = await findTokenVault(externalMint.publicKey);
+
+    // The user holds some of the external token
+    let userAccount = await createAccount(
+      provider.connection,
+      user,
+      externalMint.publicKey,
+      user.publicKey
+    );
+    await mintTo(
+      provider.connection,
+      user,
+      externalMint.publicKey,
+      userAccount,
+      user,
+      5_000_000
+    );
+
+    const tx = await program.rpc.fundReserve(
+      infoBump,
+      new anchor.BN(5_000_000),
+      {
+        accounts: {
+          vaultInfo: vaultInfo,
+          tokenVault: tokenVault,
+          tokenMint: externalMint.publicKey,
+          funder: user.publicKey,
+          funderTokenAccount: userAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [user],
+      }
+    );
+    console.log("Your transaction signature", tx);
+
+    let reserveBalance = await provider.connection.getTokenAccountBalance(
+      tokenVault
+    );
+    let info = await program.account.vaultInfo.fetch(vaultInfo);
+    console.log("Asserting the contribution reached the reserve and was recorded");
+    assert(reserveBalance.value.amount == "5000000");
+    assert(info.totalFunded.toNumber() == 5_000_000);
+  });
+
+  it("Admin sweeps the excess reserve, but never the buffer!", async () => {
+    let [vaultInfo, infoBump] = await findVaultInfo(externalMint.publicKey);
+    let [tokenVault] = await findTokenVault(externalMint.publicKey);
+    let adminAccount = await createAccount(
+      provider.connection,
+      vaultAdmin,
+      externalMint.publicKey,
+      vaultAdmin.publicKey
+    );
+
+    async function sweep(amount: number) {
+      return await program.rpc.sweepReserve(infoBump, new anchor.BN(amount), {
+        accounts: {
+          vaultInfo: vaultInfo,
+          tokenVault: tokenVault,
+          tokenMint: externalMint.publicKey,
+          vaultAdmin: vaultAdmin.publicKey,
+          destination: adminAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [vaultAdmin],
+      });
+    }
+
+    // Nothing is promised yet, so all but the 1 token (6 decimals) buffer is excess
+    await sweep(4_000_000);
+    await assert.rejects(sweep(1));
+
+    let info = await program.account.vaultInfo.fetch(vaultInfo);
+    console.log("Asserting the sweep was recorded against the funding");
+    assert(info.totalSwept.toNumber() == 4_000_000);
+
+    let reserveBalance = await provider.connection.getTokenAccountBalance(
+      tokenVault
+    );
+    console.log("Asserting the buffer stayed in the reserve");
+    assert(reserveBalance.value.amount == "1000000");
+  });
+
+  it("Admin transfer leaves the freeze authority of an external mint alone!", async () => {
+    let [vaultInfo, infoBump] = await findVaultInfo(externalMint.publicKey);
+    await proposeAdmin(vaultAdmin, user.publicKey, externalMint.publicKey);
+
+    let info = await program.account.vaultInfo.fetch(vaultInfo);
+    let mint = await getMint(provider.connection, externalMint.publicKey);
+    console.log("Asserting the freeze authority was not escrowed");
+    assert(info.pendingAdmin.equals(user.publicKey));
+    assert(mint.freezeAuthority.equals(vaultAdmin.publicKey));
+
+    await program.rpc.cancelAdminTransfer(infoBump, {
+      accounts: {
+        vaultInfo: vaultInfo,
+        tokenMint: externalMint.publicKey,
+        vaultAdmin: vaultAdmin.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [vaultAdmin],
+    });
+
+    mint = await getMint(provider.connection, externalMint.publicKey);
+    assert(mint.freezeAuthority.equals(vaultAdmin.publicKey));
+  });
+
+  async function proposeAdmin(
+    admin: anchor.web3.Keypair,
+    newAdmin: anchor.web3.PublicKey,
+    mint: anchor.web3.PublicKey = tokenMint.publicKey
+  ) {
+    let [vaultInfo, infoBump] = await findVaultInfo(mint);
+    return await program.rpc.proposeAdmin(infoBump, newAdmin, {
+      accounts: {
+        vaultInfo: vaultInfo,
+        tokenMint: mint,
+        vaultAdmin: admin.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [admin],
+    });
+  }
+
+  async function acceptAdmin(newAdmin: anchor.web3.Keypair) {
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+    return await program.rpc.acceptAdmin(infoBump, {
+      accounts: {
+        vaultInfo: vaultInfo,
+        vaultRoles: vaultRoles,
+        tokenMint: tokenMint.publicKey,
+        newAdmin: newAdmin.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [newAdmin],
+    });
+  }
+
+  it("Admin proposes a new admin and cancels!", async () => {
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+    await proposeAdmin(vaultAdmin, user.publicKey);
+
+    let info = await program.account.vaultInfo.fetch(vaultInfo);
+    let mint = await getMint(provider.connection, tokenMint.publicKey);
+    console.log("Asserting the freeze authority is escrowed in the vault");
+    assert(info.pendingAdmin.equals(user.publicKey));
+    assert(mint.freezeAuthority.equals(vaultInfo));
+
+    await program.rpc.cancelAdminTransfer(infoBump, {
+      accounts: {
+        vaultInfo: vaultInfo,
+        tokenMint: tokenMint.publicKey,
+        vaultAdmin: vaultAdmin.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [vaultAdmin],
+    });
+
+    info = await program.account.vaultInfo.fetch(vaultInfo);
+    mint = await getMint(provider.connection, tokenMint.publicKey);
+    console.log("Asserting the freeze authority went back to the admin");
+    assert(info.pendingAdmin == null);
+    assert(mint.freezeAuthority.equals(vaultAdmin.publicKey));
+
+    console.log("Asserting nobody can accept a cancelled transfer");
+    await assert.rejects(acceptAdmin(user));
+  });
+
+  it("Admin hands the vault over and gets it back!", async () => {
+    let [vaultInfo] = await findVaultInfo(tokenMint.publicKey);
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+    await proposeAdmin(vaultAdmin, user.publicKey);
+    await acceptAdmin(user);
+
+    let info = await program.account.vaultInfo.fetch(vaultInfo);
+    let mint = await getMint(provider.connection, tokenMint.publicKey);
+    let roles = await program.account.vaultRoles.fetch(vaultRoles);
+    console.log("Asserting the user is admin and holds the freeze authority and roles");
+    assert(info.vaultAdmin.equals(user.publicKey));
+    assert(info.pendingAdmin == null);
+    assert(mint.freezeAuthority.equals(user.publicKey));
+    assert(mint.mintAuthority.equals(vaultInfo));
+    assert(roles.operator.equals(user.publicKey));
+    assert(roles.riskManager.equals(user.publicKey));
+    assert(roles.pauser.equals(user.publicKey));
+
+    // Hand it back so the vault admin keypair stays in charge
+    await proposeAdmin(user, vaultAdmin.publicKey);
+    await acceptAdmin(vaultAdmin);
+
+    info = await program.account.vaultInfo.fetch(vaultInfo);
+    mint = await getMint(provider.connection, tokenMint.publicKey);
+    roles = await program.account.vaultRoles.fetch(vaultRoles);
+    assert(info.vaultAdmin.equals(vaultAdmin.publicKey));
+    assert(mint.freezeAuthority.equals(vaultAdmin.publicKey));
+    assert(roles.operator.equals(vaultAdmin.publicKey));
+    assert(roles.pauser.equals(vaultAdmin.publicKey));
+  });
+
+  it("Admin assigns roles, and nobody else can!", async () => {
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+
+    async function setRole(
+      admin: anchor.web3.Keypair,
+      role: any,
+      account: anchor.web3.PublicKey
+    ) {
+      return await program.rpc.setRole(infoBump, role, account, {
+        accounts: {
+          vaultInfo: vaultInfo,
+          vaultRoles: vaultRoles,
+          tokenMint: tokenMint.publicKey,
+          vaultAdmin: admin.publicKey,
+        },
+        signers: [admin],
+      });
+    }
+
+    await setRole(vaultAdmin, { pauser: {} }, user.publicKey);
+    let roles = await program.account.vaultRoles.fetch(vaultRoles);
+    console.log("Asserting only the pauser changed");
+    assert(roles.pauser.equals(user.publicKey));
+    assert(roles.operator.equals(vaultAdmin.publicKey));
+    assert(roles.riskManager.equals(vaultAdmin.publicKey));
+
+    await assert.rejects(setRole(user, { operator: {} }, user.publicKey));
+
+    // A delegated role survives an admin transfer, the others follow the admin
+    let newAdmin = anchor.web3.Keypair.generate();
+    await proposeAdmin(vaultAdmin, newAdmin.publicKey);
+    await acceptAdmin(newAdmin);
+    roles = await program.account.vaultRoles.fetch(vaultRoles);
+    console.log("Asserting only the roles held by the previous admin moved");
+    assert(roles.operator.equals(newAdmin.publicKey));
+    assert(roles.riskManager.equals(newAdmin.publicKey));
+    assert(roles.pauser.equals(user.publicKey));
+
+    await proposeAdmin(newAdmin, vaultAdmin.publicKey);
+    await acceptAdmin(vaultAdmin);
+
+    await setRole(vaultAdmin, { pauser: {} }, vaultAdmin.publicKey);
+  });
+
+  async function setPause(
+    pauser: anchor.web3.Keypair,
+    flags: {
+      deposits?: boolean;
+      refreshes?: boolean;
+      withdrawals?: boolean;
+      emergency?: boolean;
+    }
+  ) {
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+    return await program.rpc.setPause(
+      infoBump,
+      !!flags.deposits,
+      !!flags.refreshes,
+      !!flags.withdrawals,
+      !!flags.emergency,
+      {
+        accounts: {
+          vaultInfo: vaultInfo,
+          vaultRoles: vaultRoles,
+          tokenMint: tokenMint.publicKey,
+          pauser: pauser.publicKey,
+        },
+        signers: [pauser],
+      }
+    );
+  }
+
+  it("Pauser pauses deposits, and nobody else can!", async () => {
+    await assert.rejects(setPause(user, { deposits: true }));
+
+    await setPause(vaultAdmin, { deposits: true });
+    await assert.rejects(depositFeet(depositIndex + 3, 600, 10000));
+
+    await setPause(vaultAdmin, {});
+    await depositFeet(depositIndex + 3, 600, 10000);
+  });
+
+  it("User recovers the principal in emergency mode!", async () => {
+    await setPause(vaultAdmin, { withdrawals: true, emergency: true });
+
+    let userATA = await findAssociatedTokenAddress(
+      user.publicKey,
+      tokenMint.publicKey
+    );
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+    let [depositInfo, depositInfoBump] = await findDepositInfo(
+      user.publicKey,
+      depositIndex + 3
+    );
+    let [userVault, userVaultBump] = await findUserVault(
+      user.publicKey,
+      depositIndex + 3
+    );
+    let prevUserBalance = await provider.connection.getTokenAccountBalance(
+      userATA
+    );
+
+    // Interest payouts are frozen, but the lockout and paused withdrawals don't apply
+    await assert.rejects(withdrawEarlyFeet(depositIndex + 3));
+    let tx = await program.rpc.emergencyWithdraw(
+      infoBump,
+      depositInfoBump,
+      userVaultBump,
+      new anchor.BN(depositIndex + 3),
+      {
+        accounts: {
+          depositInfo: depositInfo,
+          vaultInfo: vaultInfo,
+          userVault: userVault,
+          tokenMint: tokenMint.publicKey,
+          depositor: user.publicKey,
+          depositorTokenAccount: userATA,
+          systemProgram: anchor.web3.SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        },
+        signers: [user],
+      }
+    );
+    console.log("Your transaction signature", tx);
+
+    let userBalance = await provider.connection.getTokenAccountBalance(userATA);
+    console.log("Asserting user got exactly the principal back");
+    assert(
+      parseInt(userBalance.value.amount) ==
+        parseInt(prevUserBalance.value.amount) + 10000
+    );
+    assert((await provider.connection.getAccountInfo(depositInfo)) == null);
+
+    await setPause(vaultAdmin, {});
+  });
+
+  async function setRateTiers(
+    riskManager: anchor.web3.Keypair,
+    tiers: { minSecondsLocked: number; annualRateBps: number }[]
+  ) {
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+    let [rateTiers] = await findRateTiers(vaultInfo);
+    return await program.rpc.setRateTiers(
+      infoBump,
+      tiers.map((tier) => ({
+        minSecondsLocked: new anchor.BN(tier.minSecondsLocked),
+        annualRateBps: new anchor.BN(tier.annualRateBps),
+      })),
+      {
+        accounts: {
+          vaultInfo: vaultInfo,
+          vaultRoles: vaultRoles,
+          rateTiers: rateTiers,
+          tokenMint: tokenMint.publicKey,
+          riskManager: riskManager.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+        signers: [riskManager],
+      }
+    );
+  }
+
+  it("Risk manager publishes rate tiers, and a deposit gets the rate of its bracket!", async () => {
+    let [vaultInfo] = await findVaultInfo(tokenMint.publicKey);
+    let [rateTiers] = await findRateTiers(vaultInfo);
+
+    await assert.rejects(
+      setRateTiers(user, [{ minSecondsLocked: 0, annualRateBps: 300 }])
+    );
+    await setRateTiers(vaultAdmin, [
+      { minSecondsLocked: 0, annualRateBps: 300 },
+      { minSecondsLocked: 500, annualRateBps: 800 },
+    ]);
+
+    await depositFeet(depositIndex + 4, 600, 10000);
+    let [depositInfo] = await findDepositInfo(user.publicKey, depositIndex + 4);
+    let info = await program.account.depositInfo.fetch(depositInfo);
+    let vault = await program.account.vaultInfo.fetch(vaultInfo);
+    let table = await program.account.rateTiers.fetch(rateTiers);
+    console.log("Asserting the box got the stored rate of its bracket");
+    assert(info.ratePerSecond.gt(vault.ratePerSecond));
+    assert(info.ratePerSecond.eq(table.ratesPerSecond[1]));
+
+    // Back to the flat vault rate
+    await setRateTiers(vaultAdmin, []);
+  });
+
+  it("User deposits into a product up to its capacity, and products-only vaults reject free-form deposits!", async () => {
+    let userATA = await findAssociatedTokenAddress(
+      user.publicKey,
+      tokenMint.publicKey
+    );
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+    let [tokenVault, reserveBump] = await findTokenVault(tokenMint.publicKey);
+    let [rateTiers] = await findRateTiers(vaultInfo);
+    let [userProfile] = await findUserProfile(user.publicKey);
+    const productId = 1;
+    let [product] = await findProduct(vaultInfo, productId);
+
+    await program.rpc.setProduct(
+      infoBump,
+      new anchor.BN(productId),
+      {
+        name: "3-second",
+        secondsLocked: new anchor.BN(3),
+        annualRateBps: new anchor.BN(800),
+        rateBasis: { apr: {} },
+        compounding: { daily: {} },
+        minDeposit: new anchor.BN(1000),
+        maxDeposit: new anchor.BN(20000),
+        capacity: new anchor.BN(30000),
+        open: true,
+      },
+      {
+        accounts: {
+          vaultInfo: vaultInfo,
+          vaultRoles: vaultRoles,
+          product: product,
+          tokenMint: tokenMint.publicKey,
+          riskManager: vaultAdmin.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+        signers: [vaultAdmin],
+      }
+    );
+
+    async function depositProduct(index: number, amount: number) {
+      let [depositInfo, depositInfoBump] = await findDepositInfo(
+        user.publicKey,
+        index
+      );
+      let [userVault, userVaultBump] = await findUserVault(
+        user.publicKey,
+        index
+      );
+      return await program.rpc.depositProduct(
+        reserveBump,
+        infoBump,
+        depositInfoBump,
+        userVaultBump,
+        new anchor.BN(productId),
+        new anchor.BN(amount),
+        {
+          accounts: {
+            deposit: {
+              userProfile: userProfile,
+              depositInfo: depositInfo,
+              vaultInfo: vaultInfo,
+              tokenVault: tokenVault,
+              rateTiers: rateTiers,
+              userVault: userVault,
+              tokenMint: tokenMint.publicKey,
+              vaultAdmin: vaultAdmin.publicKey,
+              depositor: user.publicKey,
+              depositorTokenAccount: userATA,
+              systemProgram: anchor.web3.SystemProgram.programId,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+              associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            },
+            product: product,
+          },
+          signers: [user],
+        }
+      );
+    }
+
+    await depositProduct(depositIndex + 5, 20000);
+    await assert.rejects(depositProduct(depositIndex + 6, 20000));
+
+    let [depositInfo] = await findDepositInfo(user.publicKey, depositIndex + 5);
+    let info = await program.account.depositInfo.fetch(depositInfo);
+    console.log("Asserting the box got the product's terms");
+    let productInfo = await program.account.product.fetch(product);
+    assert(info.secondsLocked.toNumber() == 3);
+    assert(info.ratePerSecond.eq(productInfo.ratePerSecond));
+    assert(info.compounding.daily);
+    assert(info.product.equals(product));
+
+    await delay(4000);
+    await withdrawFeet(depositIndex + 5, product);
+    productInfo = await program.account.product.fetch(product);
+    console.log("Asserting the withdraw released the product's capacity");
+    assert(productInfo.openCount.toNumber() == 0);
+    assert(productInfo.totalPrincipal.toNumber() == 0);
+
+    async function setProductsOnly(productsOnly: boolean) {
+      return await program.rpc.updateVaultConfig(
+        infoBump,
+        vaultConfig(annualRateBps + 100, productsOnly),
+        {
+          accounts: {
+            vaultInfo: vaultInfo,
+            vaultRoles: vaultRoles,
+            tokenMint: tokenMint.publicKey,
+            riskManager: vaultAdmin.publicKey,
+          },
+          signers: [vaultAdmin],
+        }
+      );
+    }
+
+    // Restricted to its catalog, the vault turns free-form deposits away
+    await setProductsOnly(true);
+    await assert.rejects(depositFeet(depositIndex + 6, 600, 10000), /ProductsOnly/);
+    await depositProduct(depositIndex + 6, 1000);
+    await setProductsOnly(false);
+  });
+
+  it("Risk manager switches the vault to simple interest, re-deriving its rates and those of its brackets!", async () => {
+    let [vaultInfo, infoBump] = await findVaultInfo(tokenMint.publicKey);
+    let [vaultRoles] = await findVaultRoles(vaultInfo);
+    let [rateTiers] = await findRateTiers(vaultInfo);
+
+    async function setCompounding(
+      riskManager: anchor.web3.Keypair,
+      compounding: any
+    ) {
+      return await program.rpc.setCompounding(infoBump, compounding, {
+        accounts: {
+          vaultInfo: vaultInfo,
+          vaultRoles: vaultRoles,
+          rateTiers: rateTiers,
+          tokenMint: tokenMint.publicKey,
+          riskManager: riskManager.publicKey,
+        },
+        signers: [riskManager],
+      });
+    }
+
+    await setRateTiers(vaultAdmin, [{ minSecondsLocked: 3_600, annualRateBps: 800 }]);
+    let before = await program.account.vaultInfo.fetch(vaultInfo);
+    let tiersBefore = await program.account.rateTiers.fetch(rateTiers);
+    await assert.rejects(setCompounding(user, { simple: {} }));
+    await setCompounding(vaultAdmin, { simple: {} });
+
+    let after = await program.account.vaultInfo.fetch(vaultInfo);
+    let tiersAfter = await program.account.rateTiers.fetch(rateTiers);
+    console.log("Asserting the same APYs take higher rates without compounding");
+    assert(after.ratePerSecond.gt(before.ratePerSecond));
+    assert(tiersAfter.ratesPerSecond[0].gt(tiersBefore.ratesPerSecond[0]));
+    await setRateTiers(vaultAdmin, []);
+
+    await depositFeet(depositIndex + 7, 600, 10000);
+    let [depositInfo] = await findDepositInfo(user.publicKey, depositIndex + 7);
+    let info = await program.account.depositInfo.fetch(depositInfo);
+    console.log("Asserting the box locked in simple interest");
+    assert(info.compounding.simple);
+
+    await setCompounding(vaultAdmin, { perSecond: {} });
+  });
+
+  it("Short reserve pays interest in part, queues the rest, and refills settle the claims oldest first!", async () => {
+    // The program never lets a vault promise more interest than its reserve holds, so
+    // this vault is preloaded into the validator from tests/fixtures/short-reserve (see
+    // Anchor.toml): an external mint held by the vault admin, a reserve of 600 tokens and
+    // two matured boxes of the user, each of 1,000 tokens and owed 1,000 tokens of interest.
+    const shortMint = new anchor.web3.PublicKey(
+      "6mtezEuB5PS4ZsrsLAhyp1h5vpBXmuDpfZ9fBzZFYPg8"
+    );
+    let [vaultInfo, infoBump] = await findVaultInfo(shortMint);
+    let [tokenVault, reserveBump] = await findTokenVault(shortMint);
+    let [firstClaim] = await findQueuedClaim(vaultInfo, 0);
+    let [secondClaim] = await findQueuedClaim(vaultInfo, 1);
+    let userATA = await findAssociatedTokenAddress(user.publicKey, shortMint);
+
+    async function withdrawShort(
+      index: number,
+      queuedClaim: anchor.web3.PublicKey
+    ) {
+      let [depositInfo, depositInfoBump] = await findDepositInfo(
+        user.publicKey,
+        index,
+        shortMint
+      );
+      let [userVault, userVaultBump] = await findUserVault(
+        user.publicKey,
+        index,
+        shortMint
+      );
+      return await program.rpc.withdraw(
+        reserveBump,
+        infoBump,
+        depositInfoBump,
+        userVaultBump,
+        new anchor.BN(index),
+        {
+          accounts: {
+            depositInfo: depositInfo,
+            vaultInfo: vaultInfo,
+            userVault: userVault,
+            tokenVault: tokenVault,
+            queuedClaim: queuedClaim,
+            tokenMint: shortMint,
+            vaultAdmin: vaultAdmin.publicKey,
+            depositor: user.publicKey,
+            depositorTokenAccount: userATA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            program: program.programId,
+          },
+          signers: [user],
+        }
+      );
+    }
+
+    async function balance(account: anchor.web3.PublicKey) {
+      return Number(
+        (await provider.connection.getTokenAccountBalance(account)).value.amount
+      );
+    }
+
+    // The first box gets its principal and what the reserve holds, the rest is queued
+    await withdrawShort(0, firstClaim);
+    let info = await program.account.vaultInfo.fetch(vaultInfo);
+    let claim = await program.account.queuedClaim.fetch(firstClaim);
+    console.log("Asserting the interest was paid in part and the rest queued");
+    assert((await balance(userATA)) == 1_600);
+    assert((await balance(tokenVault)) == 0);
+    assert(info.totalQueuedInterest.toNumber() == 400);
+    assert(info.queueHead.toNumber() == 0);
+    assert(info.queueTail.toNumber() == 1);
+    assert(claim.sequence.toNumber() == 0);
+    assert(claim.amount.toNumber() == 400);
+    assert(claim.claimant.equals(user.publicKey));
+    assert(claim.tokenAccount.equals(userATA));
+
+    // The second box still gets its principal, and queues all of its interest behind the first claim
+    await withdrawShort(1, secondClaim);
+    info = await program.account.vaultInfo.fetch(vaultInfo);
+    claim = await program.account.queuedClaim.fetch(secondClaim);
+    console.log("Asserting the principal was paid and a second claim was queued");
+    assert((await balance(userATA)) == 2_600);
+    assert(info.totalQueuedInterest.toNumber() == 1_400);
+    assert(info.queueTail.toNumber() == 2);
+    assert(claim.sequence.toNumber() == 1);
+    assert(claim.amount.toNumber() == 1_000);
+
+    // The vault admin holds the mint, and refills the reserve
+    let adminAccount = await createAccount(
+      provider.connection,
+      vaultAdmin,
+      shortMint,
+      vaultAdmin.publicKey
+    );
+    await mintTo(
+      provider.connection,
+      vaultAdmin,
+      shortMint,
+      adminAccount,
+      vaultAdmin,
+      2_000
+    );
+
+    async function fundShort(
+      amount: number,
+      claims: [anchor.web3.PublicKey, anchor.web3.PublicKey][]
+    ) {
+      return await program.rpc.fundReserve(infoBump, new anchor.BN(amount), {
+        accounts: {
+          vaultInfo: vaultInfo,
+          tokenVault: tokenVault,
+          tokenMint: shortMint,
+          funder: vaultAdmin.publicKey,
+          funderTokenAccount: adminAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        // Each claim is passed with the token account it is paid to and the claimant refunded its rent
+        remainingAccounts: claims
+          .map(([claim, tokenAccount]) => [claim, tokenAccount, user.publicKey])
+          .reduce(
+            (accounts, triple) => accounts.concat(triple),
+            [] as anchor.web3.PublicKey[]
+          )
+          .map((pubkey) => ({ pubkey, isWritable: true, isSigner: false })),
+        signers: [vaultAdmin],
+      });
+    }
+
+    // Claims are settled from the head of the queue
+    await assert.rejects(
+      fundShort(500, [[secondClaim, userATA]]),
+      /ClaimOutOfOrder/
+    );
+
+    // Claims can only be paid to the token account they were queued with
+    await assert.rejects(
+      fundShort(500, [[firstClaim, adminAccount]]),
+      /ClaimAccountMismatch/
+    );
+
+    // The oldest claim is paid in full and closed before the next one is paid in part
+    await fundShort(500, [
+      [firstClaim, userATA],
+      [secondClaim, userATA],
+    ]);
+    info = await program.account.vaultInfo.fetch(vaultInfo);
+    claim = await program.account.queuedClaim.fetch(secondClaim);
+    console.log("Asserting the claims were settled oldest first");
+    assert((await balance(userATA)) == 3_100);
+    assert((await balance(tokenVault)) == 0);
+    assert(info.totalQueuedInterest.toNumber() == 900);
+    assert(info.queueHead.toNumber() == 1);
+    assert((await provider.connection.getAccountInfo(firstClaim)) == null);
+    assert(claim.amount.toNumber() == 900);
+
+    await fundShort(1_000, [[secondClaim, userATA]]);
+    info = await program.account.vaultInfo.fetch(vaultInfo);
+    console.log("Asserting the queue was cleared and the rest stayed in the reserve");
+    assert((await balance(userATA)) == 4_000);
+    assert((await balance(tokenVault)) == 100);
+    assert(info.totalQueuedInterest.toNumber() == 0);
+    assert(info.queueHead.toNumber() == 2);
+    assert((await provider.connection.getAccountInfo(secondClaim)) == null);
+  });
+
+  it("User withdraws a box of the original singleton vault with its interest!", async () => {
+    // Boxes of the vault from before vaults were keyed by mint and boxes were indexed
+    // are preloaded from tests/fixtures/legacy (see Anchor.toml): a matured box of the
+    // user holding 1,000 tokens for 100 seconds, and a reserve of 10,000 tokens.
+    const legacyMint = new anchor.web3.PublicKey(
+      "7hJ7xrPRGgRRAokBgzdqJnRzz1Nu4UgmR3MzbpH4C3LD"
+    );
+    let [legacyVaultInfo, legacyInfoBump] =
+      await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from(
+            anchor.utils.bytes.utf8.encode(programConstants["VAULT_INFO_SEED"])
+          ),
+        ],
+        program.programId
+      );
+    let [tokenVault] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(
+          anchor.utils.bytes.utf8.encode(programConstants["TOKEN_VAULT_SEED"])
+        ),
+      ],
+      program.programId
+    );
+    let [depositInfo] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(
+          anchor.utils.bytes.utf8.encode(programConstants["USER_DEPOSIT_INFO"])
+        ),
+        user.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    let [userVault] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from(
+          anchor.utils.bytes.utf8.encode(programConstants["USER_VAULT_SEED"])
+        ),
+        user.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    let userATA = await findAssociatedTokenAddress(user.publicKey, legacyMint);
+
+    await program.rpc.withdrawLegacy(legacyInfoBump, {
+      accounts: {
+        legacyVaultInfo: legacyVaultInfo,
+        depositInfo: depositInfo,
+        userVault: userVault,
+        tokenVault: tokenVault,
+        tokenMint: legacyMint,
+        depositor: user.publicKey,
+        depositorTokenAccount: userATA,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      },
+      signers: [user],
+    });
+
+    // 1,000 * (1.00035^100 - 1) = 35.6, rounded down
+    console.log("Asserting the principal and interest were paid");
+    let userBalance = await provider.connection.getTokenAccountBalance(userATA);
+    let reserveBalance = await provider.connection.getTokenAccountBalance(
+      tokenVault
+    );
+    assert(userBalance.value.amount == "1035");
+    assert(reserveBalance.value.amount == "9965");
+
+    console.log("Asserting both box accounts were closed");
+    assert((await provider.connection.getAccountInfo(depositInfo)) == null);
+    assert((await provider.connection.getAccountInfo(userVault)) == null);
   });
 });